@@ -4,10 +4,13 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use auction::msg::{
-    AuctionHistoryResponse, AuctionItemResponse, ExecuteMsg, HighestBidResponse, InstantiateMsg,
-    PlaceBidMsg, QueryMsg, StartAuctionMsg,
+    AuctionHistoriesResponse, AuctionHistoryCountResponse, AuctionHistoryResponse,
+    AuctionItemResponse, BalanceResponse, BidsResponse, CommitBidMsg, ConfigResponse,
+    CurrentPriceResponse, ExecuteMsg, HighestBidResponse, InstantiateMsg, IntegrationResponse,
+    MigrateMsg, MinNextBidResponse, PlaceBidMsg, PlaceMaxBidMsg, QueryMsg, ReceiveMsg,
+    RevealBidMsg, StartAuctionMsg, UpdateConfigMsg,
 };
-use auction::state::{Bid, History, State};
+use auction::state::{Bid, BidRecord, Commitment, History, Nft, State};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -17,13 +20,30 @@ fn main() {
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
     export_schema(&schema_for!(StartAuctionMsg), &out_dir);
+    export_schema(&schema_for!(UpdateConfigMsg), &out_dir);
     export_schema(&schema_for!(PlaceBidMsg), &out_dir);
+    export_schema(&schema_for!(PlaceMaxBidMsg), &out_dir);
+    export_schema(&schema_for!(CommitBidMsg), &out_dir);
+    export_schema(&schema_for!(RevealBidMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
     export_schema(&schema_for!(HighestBidResponse), &out_dir);
     export_schema(&schema_for!(AuctionItemResponse), &out_dir);
+    export_schema(&schema_for!(CurrentPriceResponse), &out_dir);
+    export_schema(&schema_for!(MinNextBidResponse), &out_dir);
+    export_schema(&schema_for!(BalanceResponse), &out_dir);
+    export_schema(&schema_for!(IntegrationResponse), &out_dir);
+    export_schema(&schema_for!(BidsResponse), &out_dir);
     export_schema(&schema_for!(AuctionHistoryResponse), &out_dir);
+    export_schema(&schema_for!(AuctionHistoriesResponse), &out_dir);
+    export_schema(&schema_for!(AuctionHistoryCountResponse), &out_dir);
     export_schema(&schema_for!(State), &out_dir);
+    export_schema(&schema_for!(Nft), &out_dir);
     export_schema(&schema_for!(Bid), &out_dir);
+    export_schema(&schema_for!(BidRecord), &out_dir);
+    export_schema(&schema_for!(Commitment), &out_dir);
     export_schema(&schema_for!(History), &out_dir);
 }
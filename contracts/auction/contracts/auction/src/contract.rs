@@ -1,19 +1,33 @@
+use cosmwasm_schema::cw_serde;
 #[cfg(not(feature = "library"))]
-use cosmwasm_std::entry_point;
+use cosmwasm_std::{callable_points, entry_point};
 use cosmwasm_std::{
-    dynamic_link, from_binary, to_binary, Addr, BankMsg, Binary, Coin, Contract, Deps, DepsMut,
-    Empty, Env, MessageInfo, Response, StdResult, SubMsg, Timestamp, Uint128,
+    dynamic_link, from_binary, to_binary, Addr, BankMsg, Binary, Coin, Contract, CosmosMsg, Deps,
+    DepsMut, Empty, Env, Event, MessageInfo, Order, Response, StdResult, Storage, SubMsg,
+    Timestamp, Uint128, Uint256, WasmMsg,
 };
-use cw2::set_contract_version;
-use cw721::{ApprovalResponse, OwnerOfResponse};
-use cw_utils::one_coin;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Denom};
+use cw721::{ApprovalResponse, Cw721ExecuteMsg, Cw721QueryMsg, Cw721ReceiveMsg, OwnerOfResponse};
+use cw_storage_plus::Bound;
+use cw_utils::{nonpayable, one_coin};
+use semver::Version;
+use sha2::{Digest, Sha256};
 
-use crate::error::ContractError;
+use crate::error::{ContractError, TransferError};
 use crate::msg::{
-    AuctionHistoryResponse, AuctionItemResponse, ExecuteMsg, HighestBidResponse, InstantiateMsg,
-    PlaceBidMsg, QueryMsg, StartAuctionMsg,
+    AuctionHistoriesResponse, AuctionHistoryCountResponse, AuctionHistoryResponse,
+    AuctionItemResponse, AuctionStatus, BalanceResponse, BidResponse, BidsResponse, CommitBidMsg,
+    ConfigResponse, CurrentPriceResponse, ExecuteMsg, HighestBidResponse, InstantiateMsg,
+    IntegrationResponse, MigrateMsg, MinNextBidResponse, PlaceBidMsg, PlaceMaxBidMsg, QueryMsg,
+    ReceiveMsg, RevealBidMsg, StartAuctionMsg, UpdateConfigMsg,
+};
+use crate::state::{
+    bid_log, histories, AuctionType, Bid, BidIncrement, BidRecord, Commitment, Config, History,
+    Integration, Mode, Nft, PriceDecay, Royalty, State, UnrevealedDeposit, AUCTIONS, AUCTION_INDEX,
+    BALANCES, BIDS, BID_COUNTS, COMMITMENTS, CONFIG, HISTORY_INDEX, INTEGRATIONS, LEGACY_BID,
+    LEGACY_HISTORIES, LEGACY_STATE, MAX_BIDS,
 };
-use crate::state::{Bid, History, Mode, State, BID, HISTORIES, HISTORY_INDEX, STATE};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "fnsa-contracts:auction";
@@ -21,6 +35,18 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const MAX_EXPIRATION_SECONDS: u64 = 604800; // 1 week(60 * 60 * 24 * 7)
 
+// nfts in a bundle besides the first one
+const MAX_BUNDLE_SIZE: usize = 20;
+
+// the only denom of 0.1.0
+const LEGACY_DENOM: &str = "cony";
+
+const BASIS_POINTS: u64 = 10000; // 100%
+
+// pagination of list queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 pub type Extension = Option<Empty>;
 
 #[derive(Contract)]
@@ -30,7 +56,7 @@ struct Cw721Contract {
 
 #[dynamic_link(Cw721Contract)]
 trait Cw721: Contract {
-    fn transfer_nft(&self, recipient: String, token_id: String) -> bool;
+    fn transfer_nft(&self, recipient: String, token_id: String) -> Result<(), TransferError>;
     fn owner_of(&self, token_id: String, include_expired: bool) -> StdResult<Binary>;
     fn approval(
         &self,
@@ -38,6 +64,14 @@ trait Cw721: Contract {
         spender: String,
         include_expired: Option<bool>,
     ) -> StdResult<Binary>;
+    fn royalty_info(&self, token_id: String, sale_price: Uint128) -> StdResult<Binary>;
+}
+
+// response of `royalty_info`, same as cw2981-royalties
+#[cw_serde]
+struct RoyaltiesInfoResponse {
+    address: String,
+    royalty_amount: Uint128,
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -45,29 +79,145 @@ pub fn instantiate(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    _msg: InstantiateMsg,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    // reset auction state
-    STATE.save(
-        deps.storage,
-        &State {
-            mode: Mode::End,
-            end_time: Timestamp::from_nanos(0),
-            seller: Addr::unchecked(""),
-            cw721_address: Addr::unchecked(""),
-            token_id: String::from(""),
-            start_bid: 0,
-        },
-    )?;
+    let admin = msg.admin.clone().unwrap_or_else(|| info.sender.clone());
+    let config = new_config(deps.as_ref(), admin, msg)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    // reset auction index
+    AUCTION_INDEX.save(deps.storage, &0)?;
 
     // reset history index
     HISTORY_INDEX.save(deps.storage, &0)?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("owner", info.sender)
+        .add_attribute("admin", config.admin)
+        .add_attribute(
+            "denoms",
+            config
+                .denoms
+                .iter()
+                .map(denom_label)
+                .collect::<Vec<String>>()
+                .join(","),
+        )
+        .add_attribute("fee_bps", config.fee_bps.to_string())
+        .add_attribute("fee_collector", config.fee_collector))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrateError {
+            previous_contract: stored.contract,
+        });
+    }
+    let stored_version = parse_version(&stored.version)?;
+    if stored_version > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::DowngradeError {
+            stored: stored.version,
+            current: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    let mut res = Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    // 0.1.0 keeps a single auction and has no config
+    if LEGACY_STATE.may_load(deps.storage)?.is_some() {
+        res = migrate_single_auction(deps.branch(), &env, msg, res)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(res)
+}
+
+// Moves the single auction layout to auctions keyed by id and adds the number of migrated
+// histories to `res`. Each history gets its own auction id, without a bid log.
+//
+// An expired legacy auction stays in progress until its winner pays, so an auction which the
+// winner walked away from is closed here, and its NFT is returned to the seller. Bids of 0.1.0
+// are not escrowed, so there is nothing to refund.
+fn migrate_single_auction(
+    deps: DepsMut,
+    env: &Env,
+    msg: MigrateMsg,
+    mut res: Response,
+) -> Result<Response, ContractError> {
+    let legacy = LEGACY_STATE.load(deps.storage)?;
+    if legacy.mode == Mode::Progress {
+        if env.block.time <= legacy.end_time {
+            return Err(ContractError::LegacyAuctionInProgressError {});
+        }
+        res = res
+            .add_submessages(transfer_nft(
+                deps.as_ref(),
+                &legacy.cw721_address,
+                &legacy.token_id,
+                &legacy.seller,
+            )?)
+            .add_attribute("returned_token_id", legacy.token_id);
+    }
+
+    let config = match msg.config {
+        Some(config) => match config.admin.clone() {
+            Some(admin) => new_config(deps.as_ref(), admin, config)?,
+            None => return Err(ContractError::MigrateConfigError {}),
+        },
+        None => return Err(ContractError::MigrateConfigError {}),
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    let legacy_histories = LEGACY_HISTORIES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut auction_id = 0;
+    for (idx, history) in legacy_histories {
+        // the legacy entry must be removed first, it cannot be read as the new history
+        LEGACY_HISTORIES.remove(deps.storage, idx);
+        histories().save(
+            deps.storage,
+            idx,
+            &History {
+                auction_id,
+                bid_count: 0,
+                end_time: history.end_time,
+                seller: history.seller,
+                cw721_address: history.cw721_address,
+                token_id: history.token_id,
+                bundle: vec![],
+                highest_bid: Uint128::from(history.highest_bid),
+                denom: Denom::Native(LEGACY_DENOM.to_string()),
+                bidder: history.bidder,
+                fee: Uint128::zero(),
+                fee_collector: config.fee_collector.clone(),
+                royalty: Uint128::zero(),
+                royalties: vec![],
+                seller_amount: Uint128::from(history.highest_bid),
+            },
+        )?;
+        auction_id += 1;
+    }
+
+    LEGACY_STATE.remove(deps.storage);
+    LEGACY_BID.remove(deps.storage);
+    AUCTION_INDEX.save(deps.storage, &auction_id)?;
+
+    Ok(res.add_attribute("migrated_histories", auction_id.to_string()))
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    Version::parse(version).map_err(|_| ContractError::VersionError {
+        version: version.to_string(),
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -77,92 +227,454 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // only the seller cancellation, the admin messages and reveals are open while paused. the
+    // reveal phase keeps running, and sealed bids which miss it can be forfeited to the seller.
+    match msg {
+        ExecuteMsg::StartAuction(_)
+        | ExecuteMsg::ReceiveNft(_)
+        | ExecuteMsg::PlaceBid(_)
+        | ExecuteMsg::PlaceMaxBid(_)
+        | ExecuteMsg::CommitBid(_)
+        | ExecuteMsg::Receive(_)
+        | ExecuteMsg::EndAuction { .. }
+        | ExecuteMsg::Deposit {} => {
+            if CONFIG.load(deps.storage)?.paused {
+                return Err(ContractError::PausedError {});
+            }
+        }
+        _ => {}
+    }
+
     match msg {
         ExecuteMsg::StartAuction(msg) => start_auction(deps, env, info, msg),
         ExecuteMsg::PlaceBid(msg) => place_bid(deps, env, info, msg),
-        ExecuteMsg::EndAuction {} => end_auction(deps, env, info),
+        ExecuteMsg::PlaceMaxBid(msg) => place_max_bid(deps, env, info, msg),
+        ExecuteMsg::CommitBid(msg) => commit_bid(deps, env, info, msg),
+        ExecuteMsg::RevealBid(msg) => reveal_bid(deps, env, info, msg),
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::ReceiveNft(msg) => receive_nft(deps, env, info, msg),
+        ExecuteMsg::CancelAuction { auction_id } => cancel_auction(deps, env, info, auction_id),
+        ExecuteMsg::EndAuction { auction_id } => end_auction(deps, env, info, auction_id),
+        ExecuteMsg::UpdateConfig(msg) => update_config(deps, info, msg),
+        ExecuteMsg::AddCollection {
+            cw721_address,
+            integration,
+        } => add_collection(deps, info, cw721_address, integration),
+        ExecuteMsg::RemoveCollection { cw721_address } => {
+            remove_collection(deps, info, cw721_address)
+        }
+        ExecuteMsg::TransferAdmin { admin } => transfer_admin(deps, info, admin),
+        ExecuteMsg::Pause {} => set_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => set_paused(deps, info, false),
+        ExecuteMsg::EmergencyWithdraw { start_after, limit } => {
+            emergency_withdraw(deps, env, info, start_after, limit)
+        }
+        ExecuteMsg::Deposit {} => deposit(deps, info),
+        ExecuteMsg::Withdraw { denom, amount } => withdraw(deps, info, denom, amount),
+    }
+}
+
+pub fn set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut config = load_admin_config(deps.as_ref(), &info)?;
+    config.paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", if paused { "pause" } else { "unpause" })
+        .add_attribute("admin", info.sender))
+}
+
+// While paused, the admin can scan up to `limit` auctions after `start_after` at a time, closing
+// the open ones and returning each NFT to its seller and each escrowed bid or deposit to its
+// bidder. The scan is bounded by `limit` so that the gas does not grow with the auction history,
+// and the last scanned auction is emitted as the cursor of the next call.
+pub fn emergency_withdraw(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config = load_admin_config(deps.as_ref(), &info)?;
+    if !config.paused {
+        return Err(ContractError::NotPausedError {});
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let scanned = AUCTIONS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let last_auction_id = scanned.last().map(|(auction_id, _)| *auction_id);
+    let auctions = scanned
+        .into_iter()
+        .filter(|(_, state)| state.mode != Mode::End && state.mode != Mode::Canceled)
+        .collect::<Vec<_>>();
+
+    let mut res = Response::new()
+        .add_attribute("method", "emergency_withdraw")
+        .add_attribute("withdrawn", auctions.len().to_string());
+    if let Some(last_auction_id) = last_auction_id {
+        res = res.add_attribute("last_auction_id", last_auction_id.to_string());
+    }
+    for (auction_id, mut state) in auctions {
+        state.mode = Mode::Canceled;
+        AUCTIONS.save(deps.storage, auction_id, &state)?;
+
+        // return nfts to seller
+        res = res.add_submessages(transfer_lot(deps.as_ref(), &state, &state.seller)?);
+        let mut event = Event::new("emergency_withdraw")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("seller", state.seller.clone())
+            .add_attribute("cw721_address", state.cw721_address.clone())
+            .add_attribute("token_id", state.token_id.clone());
+
+        // refund escrowed deposits of sealed bids, or the highest bid otherwise
+        let refunds = if let AuctionType::SealedBid { .. } = state.auction_type {
+            COMMITMENTS
+                .prefix(auction_id)
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| item.map(|(bidder, commitment)| (bidder, commitment.deposit)))
+                .collect::<StdResult<Vec<_>>>()?
+        } else {
+            let bid = BIDS.load(deps.storage, auction_id)?;
+            let escrow = escrowed(deps.storage, auction_id, &bid)?;
+            MAX_BIDS.remove(deps.storage, auction_id);
+            bid.bidder
+                .map(|bidder| (bidder, escrow))
+                .into_iter()
+                .collect()
+        };
+        for (bidder, amount) in refunds {
+            res = res.add_submessage(payout_msg(&state.denom, &bidder, amount)?);
+            event = event
+                .add_attribute("refund", amount.to_string())
+                .add_attribute("refund_to", bidder);
+        }
+        res = res.add_event(event);
+    }
+
+    Ok(res)
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: UpdateConfigMsg,
+) -> Result<Response, ContractError> {
+    let mut config = load_admin_config(deps.as_ref(), &info)?;
+    if let Some(denoms) = msg.denoms {
+        config.denoms = denoms;
+    }
+    if let Some(max_expiration) = msg.max_expiration {
+        config.max_expiration = max_expiration;
+    }
+    if let Some(min_duration) = msg.min_duration {
+        config.min_duration = min_duration;
+    }
+    if let Some(fee_bps) = msg.fee_bps {
+        config.fee_bps = fee_bps;
+    }
+    if let Some(fee_collector) = msg.fee_collector {
+        config.fee_collector = fee_collector;
+    }
+    if let Some(min_increment) = msg.min_increment {
+        config.min_increment = Some(min_increment);
+    }
+    validate_config(deps.as_ref(), &config)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_config")
+        .add_attribute(
+            "denoms",
+            config
+                .denoms
+                .iter()
+                .map(denom_label)
+                .collect::<Vec<String>>()
+                .join(","),
+        )
+        .add_attribute("max_expiration", config.max_expiration.to_string())
+        .add_attribute("min_duration", config.min_duration.to_string())
+        .add_attribute("fee_bps", config.fee_bps.to_string())
+        .add_attribute("fee_collector", config.fee_collector))
+}
+
+pub fn add_collection(
+    deps: DepsMut,
+    info: MessageInfo,
+    cw721_address: Addr,
+    integration: Option<Integration>,
+) -> Result<Response, ContractError> {
+    let mut config = load_admin_config(deps.as_ref(), &info)?;
+    deps.api.addr_validate(cw721_address.as_str())?;
+    if config.collections.contains(&cw721_address) {
+        return Err(ContractError::DuplicateCollectionError { cw721_address });
+    }
+    config.collections.push(cw721_address.clone());
+    CONFIG.save(deps.storage, &config)?;
+    let integration = integration.unwrap_or(Integration::DynamicLink);
+    INTEGRATIONS.save(deps.storage, &cw721_address, &integration)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "add_collection")
+        .add_attribute("cw721_address", cw721_address)
+        .add_attribute("integration", integration_label(&integration)))
+}
+
+// Running auctions of a removed collection are not affected.
+pub fn remove_collection(
+    deps: DepsMut,
+    info: MessageInfo,
+    cw721_address: Addr,
+) -> Result<Response, ContractError> {
+    let mut config = load_admin_config(deps.as_ref(), &info)?;
+    if !config.collections.contains(&cw721_address) {
+        return Err(ContractError::CollectionNotAllowedError { cw721_address });
     }
+    config
+        .collections
+        .retain(|collection| *collection != cw721_address);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "remove_collection")
+        .add_attribute("cw721_address", cw721_address))
+}
+
+pub fn transfer_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: Addr,
+) -> Result<Response, ContractError> {
+    let mut config = load_admin_config(deps.as_ref(), &info)?;
+    deps.api.addr_validate(admin.as_str())?;
+    config.admin = admin.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "transfer_admin")
+        .add_attribute("previous_admin", info.sender)
+        .add_attribute("admin", admin))
 }
 
-// This auction contract can do only one auction at the same time.
 pub fn start_auction(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: StartAuctionMsg,
 ) -> Result<Response, ContractError> {
-    let s = STATE.load(deps.storage)?;
-    if s.mode == Mode::Progress {
-        return Err(ContractError::AuctionProgressError {});
+    // the approved nfts are pulled from the seller through the dynamic link
+    execute_start_auction(deps, env, info.sender, msg, false)
+}
+
+// An nft sent with `SendNft` starts the auction embedded in the message without an approval.
+// The nfts of the bundle still have to be approved.
+pub fn receive_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let msg: StartAuctionMsg = from_binary(&wrapper.msg)?;
+    if msg.cw721_address != info.sender || msg.token_id != wrapper.token_id {
+        return Err(ContractError::Unauthorized {});
     }
 
-    if msg.expiration_time > MAX_EXPIRATION_SECONDS {
+    let seller = deps.api.addr_validate(&wrapper.sender)?;
+    execute_start_auction(deps, env, seller, msg, true)
+}
+
+// Each auction is stored under its own auction id, so several auctions can run at the same time.
+// `received` tells whether the first nft is already sent to the contract.
+fn execute_start_auction(
+    deps: DepsMut,
+    env: Env,
+    seller: Addr,
+    msg: StartAuctionMsg,
+    received: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if msg.expiration_time > config.max_expiration {
         return Err(ContractError::ExpirationTimeError {
             val: msg.expiration_time,
         });
     }
+    if msg.expiration_time < config.min_duration {
+        return Err(ContractError::DurationError {
+            val: msg.expiration_time,
+        });
+    }
 
-    // set start bid and seller
-    BID.save(
-        deps.storage,
-        &Bid {
-            highest_bid: msg.start_bid.clone(),
-            bidder: info.sender.clone(),
-        },
-    )?;
+    // reserve and buy-now prices, time extension and minimum increment only apply to english auctions
+    if let AuctionType::English = msg.auction_type {
+        let floor = msg.start_bid.max(msg.reserve_price.unwrap_or_default());
+        if msg.buy_now_price.map_or(false, |price| price <= floor) {
+            return Err(ContractError::BuyNowPriceError {});
+        }
+        if let Some(increment) = &msg.min_increment {
+            validate_increment(increment)?;
+        }
+    } else if msg.reserve_price.is_some()
+        || msg.buy_now_price.is_some()
+        || msg.time_extension.is_some()
+        || msg.min_increment.is_some()
+    {
+        return Err(ContractError::AuctionTypeError {});
+    }
 
-    let contract = Cw721Contract {
-        address: msg.cw721_address.clone(),
-    };
+    let max_extension = msg
+        .time_extension
+        .as_ref()
+        .map_or(0, |ext| ext.max_extension);
+    if max_extension > config.max_expiration {
+        return Err(ContractError::ExpirationTimeError { val: max_extension });
+    }
 
-    // check owner
-    let owner =
-        from_binary::<OwnerOfResponse>(&contract.owner_of(msg.token_id.clone(), false)?)?.owner;
-    if owner != info.sender {
-        return Err(ContractError::Unauthorized {});
+    match &msg.auction_type {
+        AuctionType::English => {}
+        AuctionType::Dutch {
+            start_price,
+            floor_price,
+            decay,
+        } => {
+            let invalid_period = matches!(decay, PriceDecay::Stepwise { period: 0, .. });
+            if floor_price > start_price || invalid_period {
+                return Err(ContractError::PriceDecayError {});
+            }
+        }
+        AuctionType::SealedBid {
+            reveal_duration, ..
+        } => {
+            if *reveal_duration == 0 {
+                return Err(ContractError::RevealDurationError {});
+            }
+        }
     }
 
-    // check approval
-    let spender = from_binary::<ApprovalResponse>(&contract.approval(
-        msg.token_id.clone(),
-        env.contract.address.to_string(),
-        None,
-    )?)?
-    .approval
-    .spender;
-    if spender != env.contract.address.clone() {
-        return Err(ContractError::ApprovalError {});
+    let start_time = msg.start_time.unwrap_or(env.block.time);
+    if start_time < env.block.time
+        || start_time.seconds() - env.block.time.seconds() > config.max_expiration
+    {
+        return Err(ContractError::StartTimeError {});
     }
 
-    // transfer nft to contract
-    let is_success = contract.transfer_nft(env.contract.address.to_string(), msg.token_id.clone());
-    if !is_success {
-        return Err(ContractError::TransferNFTError {
-            sender: info.sender,
-            token_id: msg.token_id.clone(),
+    if msg.bundle.len() > MAX_BUNDLE_SIZE {
+        return Err(ContractError::BundleSizeError {
+            max: MAX_BUNDLE_SIZE,
+        });
+    }
+    let first = Nft {
+        cw721_address: msg.cw721_address.clone(),
+        token_id: msg.token_id.clone(),
+    };
+    for (i, nft) in msg.bundle.iter().enumerate() {
+        if *nft == first || msg.bundle[..i].contains(nft) {
+            return Err(ContractError::DuplicateNftError {
+                token_id: nft.token_id.clone(),
+            });
+        }
+    }
+    for nft in std::iter::once(&first).chain(msg.bundle.iter()) {
+        if !config.collections.contains(&nft.cw721_address) {
+            return Err(ContractError::CollectionNotAllowedError {
+                cw721_address: nft.cw721_address.clone(),
+            });
+        }
+    }
+
+    if !config.denoms.contains(&msg.denom) {
+        return Err(ContractError::InvalidDenomError {
+            denom: denom_label(&msg.denom),
         });
     }
 
+    let auction_id = AUCTION_INDEX.load(deps.storage)?;
+    AUCTION_INDEX.save(deps.storage, &(auction_id + 1))?;
+
+    // set start bid
+    BIDS.save(
+        deps.storage,
+        auction_id,
+        &Bid {
+            highest_bid: msg.start_bid,
+            bidder: None,
+        },
+    )?;
+
+    // a failed transfer aborts the start, so the lot is escrowed as a whole or not at all
+    let mut transfers = vec![];
+    if !received {
+        transfers.extend(pull_nft(deps.as_ref(), &env, &seller, &first)?);
+    }
+    for nft in msg.bundle.iter() {
+        transfers.extend(pull_nft(deps.as_ref(), &env, &seller, nft)?);
+    }
+
     // save auction state
-    STATE.save(
+    AUCTIONS.save(
         deps.storage,
+        auction_id,
         &State {
-            mode: Mode::Progress,
-            end_time: env.block.time.plus_seconds(msg.expiration_time),
-            seller: info.sender.clone(),
+            mode: match msg.auction_type {
+                AuctionType::SealedBid { .. } => Mode::Commit,
+                _ => Mode::Progress,
+            },
+            auction_type: msg.auction_type.clone(),
+            start_time,
+            end_time: start_time.plus_seconds(msg.expiration_time),
+            max_end_time: start_time.plus_seconds(msg.expiration_time + max_extension),
+            time_extension: msg.time_extension.clone(),
+            min_increment: match msg.auction_type {
+                AuctionType::English => msg.min_increment.or(config.min_increment),
+                _ => None,
+            },
+            seller: seller.clone(),
             cw721_address: msg.cw721_address.clone(),
             token_id: msg.token_id.clone(),
-            start_bid: msg.start_bid.clone(),
+            bundle: msg.bundle.clone(),
+            start_bid: msg.start_bid,
+            reserve_price: msg.reserve_price,
+            buy_now_price: msg.buy_now_price,
+            denom: msg.denom.clone(),
+            fee_bps: config.fee_bps,
+            fee_collector: config.fee_collector.clone(),
         },
     )?;
 
-    Ok(Response::new()
+    let mut res = Response::new()
+        .add_submessages(transfers)
         .add_attribute("method", "start_auction")
+        .add_attribute("auction_id", auction_id.to_string())
         .add_attribute("expiration_time", msg.expiration_time.to_string())
-        .add_attribute("seller", info.sender)
+        .add_attribute("seller", seller)
         .add_attribute("cw721_address", msg.cw721_address)
         .add_attribute("token_id", msg.token_id)
-        .add_attribute("start_bid", msg.start_bid.to_string()))
+        .add_attribute("auction_type", auction_type_label(&msg.auction_type))
+        .add_attribute("start_bid", msg.start_bid.to_string())
+        .add_attribute("has_reserve", msg.reserve_price.is_some().to_string())
+        .add_attribute("denom", denom_label(&msg.denom));
+    if let Some(price) = msg.buy_now_price {
+        res = res.add_attribute("buy_now_price", price.to_string());
+    }
+    if !msg.bundle.is_empty() {
+        res = res.add_attribute("bundle_size", msg.bundle.len().to_string());
+    }
+    if start_time > env.block.time {
+        res = res.add_attribute("start_time", start_time.to_string());
+    }
+
+    Ok(res)
 }
 
 pub fn place_bid(
@@ -171,155 +683,1520 @@ pub fn place_bid(
     info: MessageInfo,
     msg: PlaceBidMsg,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if state.mode != Mode::Progress {
-        return Err(ContractError::AuctionNoProgressError {});
-    }
+    // the bid is escrowed in the contract until the auction ends or it is outbid
+    let coin = one_coin(&info).map_err(|_| ContractError::FundsError {})?;
+    execute_place_bid(
+        deps,
+        env,
+        info.sender,
+        Denom::Native(coin.denom),
+        coin.amount,
+        BidOrder::Bid(msg),
+        false,
+    )
+}
 
-    if state.end_time < env.block.time {
-        return Err(ContractError::AuctionTimeError {});
+// The whole max bid is escrowed until the auction ends or it is outbid.
+pub fn place_max_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: PlaceMaxBidMsg,
+) -> Result<Response, ContractError> {
+    let coin = one_coin(&info).map_err(|_| ContractError::FundsError {})?;
+    execute_place_bid(
+        deps,
+        env,
+        info.sender,
+        Denom::Native(coin.denom),
+        coin.amount,
+        BidOrder::MaxBid(msg),
+        false,
+    )
+}
+
+// cw20 tokens sent with `Send` are escrowed in the same way as native funds.
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::PlaceBid(msg) => execute_place_bid(
+            deps,
+            env,
+            sender,
+            Denom::Cw20(info.sender),
+            wrapper.amount,
+            BidOrder::Bid(msg),
+            false,
+        ),
+        ReceiveMsg::PlaceMaxBid(msg) => execute_place_bid(
+            deps,
+            env,
+            sender,
+            Denom::Cw20(info.sender),
+            wrapper.amount,
+            BidOrder::MaxBid(msg),
+            false,
+        ),
+        ReceiveMsg::CommitBid(msg) => execute_commit_bid(
+            deps,
+            env,
+            sender,
+            Denom::Cw20(info.sender),
+            wrapper.amount,
+            msg,
+        ),
+        ReceiveMsg::Deposit {} => {
+            execute_deposit(deps, sender, Denom::Cw20(info.sender), wrapper.amount)
+        }
     }
+}
 
-    let balance = deps.querier.query_balance(info.sender.clone(), "cony")?;
-    if balance.amount < Uint128::from(msg.bid.clone()) {
-        return Err(ContractError::InsufficientBalanceError {});
+pub fn deposit(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let coin = one_coin(&info).map_err(|_| ContractError::FundsError {})?;
+    execute_deposit(deps, info.sender, Denom::Native(coin.denom), coin.amount)
+}
+
+fn execute_deposit(
+    deps: DepsMut,
+    owner: Addr,
+    denom: Denom,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.denoms.contains(&denom) {
+        return Err(ContractError::FundsError {});
     }
+    let balance = add_balance(deps.storage, &owner, &denom, amount)?;
 
-    // update bid if it is higher than previous highest bid
-    BID.update(deps.storage, |mut b| {
-        if b.highest_bid >= msg.bid.clone() {
-            return Err(ContractError::InvalidBidError {
-                bid: msg.bid,
-                highest_bid: b.highest_bid,
-            });
-        }
+    Ok(Response::new()
+        .add_attribute("method", "deposit")
+        .add_attribute("owner", owner)
+        .add_attribute("denom", denom_label(&denom))
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("balance", balance.to_string()))
+}
 
-        b.highest_bid = msg.bid;
-        b.bidder = info.sender.clone();
-        Ok(b)
-    })?;
+pub fn withdraw(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Denom,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::FundsError {})?;
+    let balance = BALANCES
+        .may_load(deps.storage, (&info.sender, &balance_key(&denom)))?
+        .unwrap_or_default();
+    let amount = amount.unwrap_or(balance);
+    if amount.is_zero() {
+        return Err(ContractError::FundsError {});
+    }
+    let balance = sub_balance(deps.storage, &info.sender, &denom, amount)?;
 
     Ok(Response::new()
-        .add_attribute("method", "place_bid")
-        .add_attribute("bid", msg.bid.to_string())
-        .add_attribute("bidder", info.sender))
+        .add_submessage(payout_msg(&denom, &info.sender, amount)?)
+        .add_attribute("method", "withdraw")
+        .add_attribute("owner", info.sender)
+        .add_attribute("denom", denom_label(&denom))
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("balance", balance.to_string()))
+}
+
+// A bid placed with `PlaceBid`, or a max bid placed with `PlaceMaxBid`, which bids for its
+// bidder just enough to lead the auction, up to the max.
+enum BidOrder {
+    Bid(PlaceBidMsg),
+    MaxBid(PlaceMaxBidMsg),
 }
 
-pub fn end_auction(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
+fn execute_place_bid(
+    deps: DepsMut,
+    env: Env,
+    bidder: Addr,
+    denom: Denom,
+    amount: Uint128,
+    order: BidOrder,
+    credit: bool,
+) -> Result<Response, ContractError> {
+    let (auction_id, bid, is_max_bid) = match order {
+        BidOrder::Bid(msg) => (msg.auction_id, msg.bid, false),
+        BidOrder::MaxBid(msg) => (msg.auction_id, msg.max_bid, true),
+    };
+
+    let mut state = AUCTIONS.load(deps.storage, auction_id)?;
+    match state.auction_type {
+        AuctionType::SealedBid { .. } => return Err(ContractError::AuctionTypeError {}),
+        // max bids only apply to english auctions
+        AuctionType::Dutch { .. } if is_max_bid => return Err(ContractError::AuctionTypeError {}),
+        _ => {}
+    }
+
     if state.mode != Mode::Progress {
         return Err(ContractError::AuctionNoProgressError {});
     }
 
-    if state.end_time.clone() >= env.block.time {
+    if env.block.time < state.start_time {
+        return Err(ContractError::AuctionNotStartedError {});
+    }
+
+    if state.end_time < env.block.time {
         return Err(ContractError::AuctionTimeError {});
     }
 
-    // only highest bidder can end auction
-    let bid = BID.load(deps.storage)?;
-    if bid.bidder != info.sender.clone() {
+    // the seller cannot bid on their own auction
+    if bidder == state.seller {
         return Err(ContractError::Unauthorized {});
     }
 
-    // reset auction state
-    STATE.save(
-        deps.storage,
-        &State {
-            mode: Mode::End,
-            end_time: Timestamp::from_nanos(0),
-            seller: Addr::unchecked(""),
-            cw721_address: Addr::unchecked(""),
-            token_id: String::from(""),
-            start_bid: 0,
-        },
-    )?;
-
-    if let Ok(coin) = one_coin(&info) {
-        if coin.denom != "cony" || coin.amount < Uint128::from(bid.highest_bid.clone()) {
-            return Err(ContractError::InsufficientBalanceError {});
-        }
-    } else {
+    if denom != state.denom || amount != bid {
         return Err(ContractError::FundsError {});
     }
 
-    let bank_msg = BankMsg::Send {
-        to_address: state.seller.to_string(),
-        amount: vec![Coin {
-            denom: String::from("cony"),
-            amount: Uint128::from(bid.highest_bid),
-        }],
+    // nfts of standard collections can only be moved with messages, so a callable point rejects
+    // a bid which settles such a lot right away, before anything is written
+    let settles = match state.auction_type {
+        AuctionType::Dutch { .. } => true,
+        _ => state.buy_now_price.map_or(false, |price| bid >= price),
     };
+    if credit && settles {
+        for nft in lot(&state) {
+            if integration(deps.storage, &nft.cw721_address)? == Integration::Standard {
+                return Err(ContractError::CallableTransferError {});
+            }
+        }
+    }
 
-    // transfer nft to bidder or seller
-    let contract = Cw721Contract {
-        address: state.cw721_address.clone(),
+    let prev = BIDS.load(deps.storage, auction_id)?;
+    let mut res = Response::new().add_attribute("method", "place_bid");
+    let mut highest_bidder = bidder.clone();
+    let mut highest_bid = bid;
+
+    let buy_price = if let AuctionType::Dutch { .. } = state.auction_type {
+        // the first bid at or above the asking price of a dutch auction buys the NFT right away
+        let price = current_price(&state, &prev, env.block.time);
+        if bid < price {
+            return Err(ContractError::InvalidBidError {
+                bid,
+                highest_bid: price,
+            });
+        }
+        Some(price)
+    } else {
+        // update bid if it is higher than previous highest bid
+        if prev.highest_bid >= bid {
+            return Err(ContractError::InvalidBidError {
+                bid,
+                highest_bid: prev.highest_bid,
+            });
+        }
+
+        // the bid must also beat the highest bid by the minimum increment, unless it buys now
+        let min_bid = min_next_bid(&state, &prev, env.block.time);
+        let buys_now = state.buy_now_price.map_or(false, |price| bid >= price);
+        if bid < min_bid && !buys_now {
+            return Err(ContractError::BidIncrementError { bid, min_bid });
+        }
+
+        // a bid at or above the buy-now price ends the auction right away
+        let buy_price = state.buy_now_price.filter(|price| bid >= *price);
+
+        let prev_max = MAX_BIDS.may_load(deps.storage, auction_id)?;
+        let replaces_own_max = prev.bidder.as_ref() == Some(&bidder) && prev_max.is_some();
+        // rivals were refunded against the max, so the highest bidder cannot lower it
+        if let Some(max) = prev_max.filter(|_| replaces_own_max) {
+            if bid < max {
+                return Err(ContractError::MaxBidError { bid, max_bid: max });
+            }
+        }
+        match (prev.bidder.clone(), prev_max) {
+            // the max bid of the highest bidder covers the new bid, so the highest bidder keeps
+            // the lead just above the new bid, and the new bid is refunded
+            (Some(leader), Some(max)) if buy_price.is_none() && bid <= max && !replaces_own_max => {
+                log_bid(deps.storage, &env, auction_id, &bidder, bid)?;
+                res = pay(deps.storage, res, credit, &state.denom, &bidder, amount)?
+                    .add_attribute("refund", amount.to_string())
+                    .add_attribute("refund_to", bidder.clone());
+                highest_bidder = leader;
+                highest_bid = proxy_bid(&state, outbid(&state, bid), max);
+            }
+            (prev_bidder, prev_max) => {
+                // refund the outbid bidder, including the rest of its max bid
+                if let Some(prev_bidder) = prev_bidder {
+                    let escrow = prev_max.unwrap_or(prev.highest_bid);
+                    res = pay(
+                        deps.storage,
+                        res,
+                        credit,
+                        &state.denom,
+                        &prev_bidder,
+                        escrow,
+                    )?
+                    .add_attribute("refund", escrow.to_string())
+                    .add_attribute("refund_to", prev_bidder);
+                }
+
+                match buy_price {
+                    // a max bid which reaches the buy-now price buys at that price
+                    Some(price) if is_max_bid => {
+                        highest_bid = price;
+                        MAX_BIDS.remove(deps.storage, auction_id);
+                    }
+                    None if is_max_bid => {
+                        // bid just enough to beat the previous max bid, or the minimum bid. the
+                        // highest bidder replacing its own max keeps its current bid
+                        let floor = match prev_max {
+                            _ if replaces_own_max => prev.highest_bid,
+                            Some(max) => outbid(&state, max),
+                            None => min_bid,
+                        };
+                        highest_bid = proxy_bid(&state, floor, bid);
+                        MAX_BIDS.save(deps.storage, auction_id, &bid)?;
+                    }
+                    _ => MAX_BIDS.remove(deps.storage, auction_id),
+                }
+            }
+        }
+
+        buy_price
     };
-    let is_success = contract.transfer_nft(info.sender.to_string(), state.token_id.clone());
-    if !is_success {
-        return Err(ContractError::TransferNFTError {
-            sender: env.contract.address,
-            token_id: state.token_id.clone(),
-        });
+
+    // max bids are logged at the price they bid, not at the max, and a max bid replaced at the
+    // same price is not logged again
+    if highest_bid != prev.highest_bid || prev.bidder.as_ref() != Some(&highest_bidder) {
+        log_bid(deps.storage, &env, auction_id, &highest_bidder, highest_bid)?;
     }
 
-    // add auction history
-    let idx = HISTORY_INDEX.load(deps.storage)?;
-    HISTORIES.save(
+    if let Some(price) = buy_price {
+        if bid > price {
+            res = pay(
+                deps.storage,
+                res,
+                credit,
+                &state.denom,
+                &bidder,
+                bid - price,
+            )?
+            .add_attribute("refund", (bid - price).to_string())
+            .add_attribute("refund_to", bidder.clone());
+        }
+
+        state.end_time = env.block.time;
+        return settle(
+            deps,
+            auction_id,
+            state,
+            Bid {
+                highest_bid: price,
+                bidder: Some(bidder),
+            },
+            res,
+            credit,
+        );
+    }
+
+    BIDS.save(
         deps.storage,
-        idx,
-        &History {
-            end_time: state.end_time,
-            seller: state.seller,
-            cw721_address: state.cw721_address,
-            token_id: state.token_id,
-            highest_bid: bid.highest_bid,
-            bidder: bid.bidder.clone(),
+        auction_id,
+        &Bid {
+            highest_bid,
+            bidder: Some(highest_bidder.clone()),
         },
     )?;
-    HISTORY_INDEX.save(deps.storage, &(idx + 1))?;
 
-    Ok(Response::new()
-        .add_submessage(SubMsg::new(bank_msg))
-        .add_attribute("method", "end_auction")
-        .add_attribute("highest_bid", bid.highest_bid.to_string())
-        .add_attribute("bidder", bid.bidder))
-}
+    // a bid inside the extension window pushes the end time out, up to the max end time
+    if let Some(extension) = &state.time_extension {
+        if state.end_time.seconds() - env.block.time.seconds() < extension.window {
+            let end_time = env
+                .block
+                .time
+                .plus_seconds(extension.duration)
+                .max(state.end_time)
+                .min(state.max_end_time);
+            if end_time != state.end_time {
+                state.end_time = end_time;
+                AUCTIONS.save(deps.storage, auction_id, &state)?;
+                res = res.add_attribute("end_time", end_time.to_string());
+            }
+        }
+    }
+
+    // the max of a max bid stays secret
+    if !is_max_bid {
+        res = res.add_attribute("bid", bid.to_string());
+    }
+    Ok(res
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("bidder", bidder)
+        .add_attribute("highest_bid", highest_bid.to_string())
+        .add_attribute("highest_bidder", highest_bidder))
+}
+
+// Sealed bids are committed as a hash of the bid with a deposit which covers the bid.
+pub fn commit_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: CommitBidMsg,
+) -> Result<Response, ContractError> {
+    let coin = one_coin(&info).map_err(|_| ContractError::FundsError {})?;
+    execute_commit_bid(
+        deps,
+        env,
+        info.sender,
+        Denom::Native(coin.denom),
+        coin.amount,
+        msg,
+    )
+}
+
+fn execute_commit_bid(
+    deps: DepsMut,
+    env: Env,
+    bidder: Addr,
+    denom: Denom,
+    amount: Uint128,
+    msg: CommitBidMsg,
+) -> Result<Response, ContractError> {
+    let state = AUCTIONS.load(deps.storage, msg.auction_id)?;
+    if state.mode != Mode::Commit {
+        return Err(ContractError::AuctionNoProgressError {});
+    }
+
+    if env.block.time < state.start_time {
+        return Err(ContractError::AuctionNotStartedError {});
+    }
+
+    if state.end_time < env.block.time {
+        return Err(ContractError::AuctionTimeError {});
+    }
+
+    if bidder == state.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if denom != state.denom {
+        return Err(ContractError::FundsError {});
+    }
+
+    // a SHA-256 hash, see `bid_commitment`
+    if msg.commitment.len() != 32 {
+        return Err(ContractError::InvalidCommitmentError {});
+    }
+
+    let key = (msg.auction_id, &bidder);
+    if COMMITMENTS.has(deps.storage, key) {
+        return Err(ContractError::AlreadyCommittedError {});
+    }
+    COMMITMENTS.save(
+        deps.storage,
+        key,
+        &Commitment {
+            commitment: msg.commitment,
+            deposit: amount,
+            revealed_bid: None,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "commit_bid")
+        .add_attribute("auction_id", msg.auction_id.to_string())
+        .add_attribute("deposit", amount.to_string())
+        .add_attribute("bidder", bidder))
+}
+
+pub fn reveal_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: RevealBidMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::FundsError {})?;
+
+    let mut state = AUCTIONS.load(deps.storage, msg.auction_id)?;
+    if state.mode != Mode::Commit && state.mode != Mode::Reveal {
+        return Err(ContractError::AuctionNoProgressError {});
+    }
+
+    // the reveal phase starts at the end time and lasts for the reveal duration
+    if env.block.time <= state.end_time || closing_time(&state) < env.block.time {
+        return Err(ContractError::AuctionTimeError {});
+    }
+    if state.mode == Mode::Commit {
+        state.mode = Mode::Reveal;
+        AUCTIONS.save(deps.storage, msg.auction_id, &state)?;
+    }
+
+    let key = (msg.auction_id, &info.sender);
+    let mut commitment = COMMITMENTS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::NoCommitmentError {})?;
+    if commitment.revealed_bid.is_some()
+        || commitment.commitment != bid_commitment(msg.auction_id, &info.sender, msg.bid, &msg.salt)
+    {
+        return Err(ContractError::CommitmentMismatchError {});
+    }
+
+    if commitment.deposit < msg.bid {
+        return Err(ContractError::DepositError {
+            bid: msg.bid,
+            deposit: commitment.deposit,
+        });
+    }
+    if msg.bid < state.start_bid {
+        return Err(ContractError::InvalidBidError {
+            bid: msg.bid,
+            highest_bid: state.start_bid,
+        });
+    }
+
+    commitment.revealed_bid = Some(msg.bid);
+    COMMITMENTS.save(deps.storage, key, &commitment)?;
+    log_bid(deps.storage, &env, msg.auction_id, &info.sender, msg.bid)?;
+
+    // keep the highest revealed bid, the earliest reveal wins a tie
+    let highest = BIDS.load(deps.storage, msg.auction_id)?;
+    if highest.bidder.is_none() || highest.highest_bid < msg.bid {
+        BIDS.save(
+            deps.storage,
+            msg.auction_id,
+            &Bid {
+                highest_bid: msg.bid,
+                bidder: Some(info.sender.clone()),
+            },
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "reveal_bid")
+        .add_attribute("auction_id", msg.auction_id.to_string())
+        .add_attribute("bid", msg.bid.to_string())
+        .add_attribute("bidder", info.sender))
+}
+
+// The seller can cancel an auction and take the NFT back as long as nobody has bid on it.
+pub fn cancel_auction(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    auction_id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::FundsError {})?;
+
+    let mut state = AUCTIONS.load(deps.storage, auction_id)?;
+    if state.mode == Mode::End || state.mode == Mode::Canceled {
+        return Err(ContractError::AuctionNoProgressError {});
+    }
+
+    if info.sender != state.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let bid = BIDS.load(deps.storage, auction_id)?;
+    let has_commitments = COMMITMENTS
+        .prefix(auction_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if bid.bidder.is_some() || has_commitments {
+        return Err(ContractError::AuctionHasBidsError {});
+    }
+
+    state.mode = Mode::Canceled;
+    AUCTIONS.save(deps.storage, auction_id, &state)?;
+
+    // return nfts to seller
+    let transfers = transfer_lot(deps.as_ref(), &state, &state.seller)?;
+
+    Ok(Response::new()
+        .add_submessages(transfers)
+        .add_attribute("method", "cancel_auction")
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("seller", state.seller))
+}
+
+pub fn end_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_id: u64,
+) -> Result<Response, ContractError> {
+    let state = AUCTIONS.load(deps.storage, auction_id)?;
+    if state.mode == Mode::End || state.mode == Mode::Canceled {
+        return Err(ContractError::AuctionNoProgressError {});
+    }
+
+    if closing_time(&state) >= env.block.time {
+        return Err(ContractError::AuctionTimeError {});
+    }
+
+    // anyone can end the auction, since the winning bid is already escrowed
+    // and no funds are expected here
+    nonpayable(&info).map_err(|_| ContractError::FundsError {})?;
+
+    let mut bid = BIDS.load(deps.storage, auction_id)?;
+    let reserve_met = is_reserve_met(&state, &bid);
+    let mut res = Response::new()
+        .add_attribute("method", "end_auction")
+        .add_attribute("settled_by", info.sender);
+
+    // refund the highest bidder and return the NFT to the seller if the reserve price is not met
+    if !reserve_met {
+        let escrow = escrowed(deps.storage, auction_id, &bid)?;
+        MAX_BIDS.remove(deps.storage, auction_id);
+        if let Some(bidder) = bid.bidder.take() {
+            res = res
+                .add_submessage(payout_msg(&state.denom, &bidder, escrow)?)
+                .add_attribute("refund", escrow.to_string())
+                .add_attribute("refund_to", bidder);
+        }
+    }
+
+    if let AuctionType::SealedBid {
+        unrevealed_deposit, ..
+    } = &state.auction_type
+    {
+        let (price, msgs) =
+            close_sealed_bid(deps.as_ref(), auction_id, &state, &bid, unrevealed_deposit)?;
+        bid.highest_bid = price;
+        res = res.add_submessages(msgs);
+    }
+
+    settle(deps, auction_id, state, bid, res, false)
+}
+
+// Charges the winner of a sealed-bid auction the second highest revealed bid, or the start bid
+// when nobody else revealed, and returns the remaining deposits.
+fn close_sealed_bid(
+    deps: Deps,
+    auction_id: u64,
+    state: &State,
+    winner: &Bid,
+    unrevealed_deposit: &UnrevealedDeposit,
+) -> Result<(Uint128, Vec<SubMsg>), ContractError> {
+    let commitments = COMMITMENTS
+        .prefix(auction_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let price = commitments
+        .iter()
+        .filter(|(bidder, _)| Some(bidder) != winner.bidder.as_ref())
+        .filter_map(|(_, commitment)| commitment.revealed_bid)
+        .max()
+        .unwrap_or(state.start_bid);
+
+    let mut msgs = vec![];
+    let mut forfeited = Uint128::zero();
+    for (bidder, commitment) in commitments {
+        let refund = if Some(&bidder) == winner.bidder.as_ref() {
+            commitment.deposit - price
+        } else if commitment.revealed_bid.is_some()
+            || *unrevealed_deposit == UnrevealedDeposit::Refund
+        {
+            commitment.deposit
+        } else {
+            forfeited += commitment.deposit;
+            Uint128::zero()
+        };
+
+        if !refund.is_zero() {
+            msgs.push(payout_msg(&state.denom, &bidder, refund)?);
+        }
+    }
+    if !forfeited.is_zero() {
+        msgs.push(payout_msg(&state.denom, &state.seller, forfeited)?);
+    }
+
+    Ok((price, msgs))
+}
+
+// Closes the auction, pays the seller from escrow and transfers the NFT to the winner.
+// Without a winning bid, the NFT is returned to the seller and no history is recorded.
+// With `credit`, the payouts are credited to the balances instead of being sent.
+fn settle(
+    deps: DepsMut,
+    auction_id: u64,
+    mut state: State,
+    bid: Bid,
+    mut res: Response,
+    credit: bool,
+) -> Result<Response, ContractError> {
+    // close auction
+    state.mode = Mode::End;
+    AUCTIONS.save(deps.storage, auction_id, &state)?;
+    BIDS.save(deps.storage, auction_id, &bid)?;
+
+    let bidder = match bid.bidder {
+        Some(bidder) => bidder,
+        None => {
+            // return nfts to seller
+            let transfers = transfer_lot(deps.as_ref(), &state, &state.seller)?;
+
+            return Ok(res
+                .add_submessages(transfers)
+                .add_attribute("auction_id", auction_id.to_string())
+                .add_attribute("sold", "false"));
+        }
+    };
+
+    // split the escrowed price into platform fee, creator royalties and seller payout. the fee is
+    // the one of the config when the auction started
+    let price = bid.highest_bid;
+    let fee = price.multiply_ratio(state.fee_bps, BASIS_POINTS);
+
+    // the price of a bundle is split evenly for the royalty of each nft,
+    // and the first nft takes the remainder
+    let nfts = lot(&state);
+    let share = price / Uint128::from(nfts.len() as u128);
+    let mut royalty = Uint128::zero();
+    let mut royalties = vec![];
+    for (i, nft) in nfts.iter().enumerate() {
+        let sale_price = if i == 0 {
+            price - share * Uint128::from(nfts.len() as u128 - 1)
+        } else {
+            share
+        };
+        if let Some((address, amount)) =
+            query_royalty(deps.as_ref(), &nft.cw721_address, &nft.token_id, sale_price)?
+        {
+            let amount = amount.min(price - fee - royalty);
+            if !amount.is_zero() {
+                royalty += amount;
+                royalties.push(Royalty { address, amount });
+            }
+        }
+    }
+    let seller_amount = price - fee - royalty;
+
+    if !fee.is_zero() {
+        res = pay(
+            deps.storage,
+            res,
+            credit,
+            &state.denom,
+            &state.fee_collector,
+            fee,
+        )?;
+    }
+    for leg in royalties.iter() {
+        res = pay(
+            deps.storage,
+            res,
+            credit,
+            &state.denom,
+            &leg.address,
+            leg.amount,
+        )?;
+    }
+    if !seller_amount.is_zero() {
+        res = pay(
+            deps.storage,
+            res,
+            credit,
+            &state.denom,
+            &state.seller,
+            seller_amount,
+        )?;
+    }
+
+    // the rest of a max bid goes back to the winner, none when a rival bid up to the max
+    if let Some(max) = MAX_BIDS.may_load(deps.storage, auction_id)? {
+        MAX_BIDS.remove(deps.storage, auction_id);
+        let refund = max - price;
+        if !refund.is_zero() {
+            res = pay(deps.storage, res, credit, &state.denom, &bidder, refund)?
+                .add_attribute("refund", refund.to_string())
+                .add_attribute("refund_to", bidder.clone());
+        }
+    }
+
+    // transfer nfts to bidder
+    let transfers = transfer_lot(deps.as_ref(), &state, &bidder)?;
+    if credit && !transfers.is_empty() {
+        return Err(ContractError::CallableTransferError {});
+    }
+    res = res.add_submessages(transfers);
+
+    // add auction history, the bid log is kept under the auction id
+    let idx = HISTORY_INDEX.load(deps.storage)?;
+    let bid_count = BID_COUNTS
+        .may_load(deps.storage, auction_id)?
+        .unwrap_or_default();
+    histories().save(
+        deps.storage,
+        idx,
+        &History {
+            auction_id,
+            bid_count,
+            end_time: state.end_time,
+            seller: state.seller.clone(),
+            cw721_address: state.cw721_address,
+            token_id: state.token_id,
+            bundle: state.bundle,
+            highest_bid: bid.highest_bid,
+            denom: state.denom,
+            bidder: bidder.clone(),
+            fee,
+            fee_collector: state.fee_collector.clone(),
+            royalty,
+            royalties: royalties.clone(),
+            seller_amount,
+        },
+    )?;
+    HISTORY_INDEX.save(deps.storage, &(idx + 1))?;
+
+    res = res
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("sold", "true")
+        .add_attribute("highest_bid", bid.highest_bid.to_string())
+        .add_attribute("bidder", bidder)
+        .add_attribute("fee", fee.to_string())
+        .add_attribute("fee_collector", state.fee_collector)
+        .add_attribute("royalty", royalty.to_string());
+    for leg in royalties {
+        res = res
+            .add_attribute("royalty_address", leg.address)
+            .add_attribute("royalty_amount", leg.amount.to_string());
+    }
+    Ok(res
+        .add_attribute("seller", state.seller)
+        .add_attribute("seller_amount", seller_amount.to_string()))
+}
+
+// Reads the creator royalty for `price` from the NFT contract through the dynamic link.
+// A royalty without a valid payment address is not paid, and only collections added with
+// `DynamicLinkWithRoyalty` pay one.
+fn query_royalty(
+    deps: Deps,
+    cw721_address: &Addr,
+    token_id: &str,
+    price: Uint128,
+) -> StdResult<Option<(Addr, Uint128)>> {
+    if integration(deps.storage, cw721_address)? != Integration::DynamicLinkWithRoyalty {
+        return Ok(None);
+    }
+    let contract = Cw721Contract {
+        address: cw721_address.clone(),
+    };
+    let royalty: RoyaltiesInfoResponse =
+        from_binary(&contract.royalty_info(token_id.to_string(), price)?)?;
+    if royalty.royalty_amount.is_zero() {
+        return Ok(None);
+    }
+    let address = match deps.api.addr_validate(&royalty.address) {
+        Ok(address) => address,
+        Err(_) => return Ok(None),
+    };
+    Ok(Some((address, royalty.royalty_amount)))
+}
+
+// Appends a bid to the bid log of the auction.
+fn log_bid(
+    storage: &mut dyn Storage,
+    env: &Env,
+    auction_id: u64,
+    bidder: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    let seq = BID_COUNTS
+        .may_load(storage, auction_id)?
+        .unwrap_or_default();
+    bid_log().save(
+        storage,
+        (auction_id, seq),
+        &BidRecord {
+            bidder: bidder.clone(),
+            amount,
+            time: env.block.time,
+            height: env.block.height,
+        },
+    )?;
+    BID_COUNTS.save(storage, auction_id, &(seq + 1))
+}
+
+// Returns the nfts of the auction, the first one followed by the bundle.
+fn lot(state: &State) -> Vec<Nft> {
+    let mut nfts = vec![Nft {
+        cw721_address: state.cw721_address.clone(),
+        token_id: state.token_id.clone(),
+    }];
+    nfts.extend(state.bundle.iter().cloned());
+    nfts
+}
+
+// Transfers every nft of the auction and returns the transfer messages of standard collections.
+fn transfer_lot(deps: Deps, state: &State, recipient: &Addr) -> Result<Vec<SubMsg>, ContractError> {
+    let mut msgs = vec![];
+    for nft in lot(state) {
+        msgs.extend(transfer_nft(
+            deps,
+            &nft.cw721_address,
+            &nft.token_id,
+            recipient,
+        )?);
+    }
+    Ok(msgs)
+}
+
+// Checks that `seller` owns the approved nft and moves it to the contract.
+fn pull_nft(
+    deps: Deps,
+    env: &Env,
+    seller: &Addr,
+    nft: &Nft,
+) -> Result<Option<SubMsg>, ContractError> {
+    let (owner, approval) = match integration(deps.storage, &nft.cw721_address)? {
+        Integration::DynamicLink | Integration::DynamicLinkWithRoyalty => {
+            let contract = Cw721Contract {
+                address: nft.cw721_address.clone(),
+            };
+            let owner: OwnerOfResponse =
+                from_binary(&contract.owner_of(nft.token_id.clone(), false)?)?;
+            let approval: ApprovalResponse = from_binary(&contract.approval(
+                nft.token_id.clone(),
+                env.contract.address.to_string(),
+                None,
+            )?)?;
+            (owner, approval)
+        }
+        Integration::Standard => {
+            let owner: OwnerOfResponse = deps.querier.query_wasm_smart(
+                &nft.cw721_address,
+                &Cw721QueryMsg::OwnerOf {
+                    token_id: nft.token_id.clone(),
+                    include_expired: Some(false),
+                },
+            )?;
+            let approval: ApprovalResponse = deps.querier.query_wasm_smart(
+                &nft.cw721_address,
+                &Cw721QueryMsg::Approval {
+                    token_id: nft.token_id.clone(),
+                    spender: env.contract.address.to_string(),
+                    include_expired: None,
+                },
+            )?;
+            (owner, approval)
+        }
+    };
+
+    // check owner
+    if owner.owner != *seller {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // check approval
+    if approval.approval.spender != env.contract.address {
+        return Err(ContractError::ApprovalError {});
+    }
+
+    // transfer nft to contract
+    transfer_nft(
+        deps,
+        &nft.cw721_address,
+        &nft.token_id,
+        &env.contract.address,
+    )
+}
+
+// Transfers the NFT right away through the dynamic link, or returns the
+// `TransferNft` message for a standard collection. A failed message reverts the whole
+// transaction, like a failed dynamic link transfer.
+fn transfer_nft(
+    deps: Deps,
+    cw721_address: &Addr,
+    token_id: &str,
+    recipient: &Addr,
+) -> Result<Option<SubMsg>, ContractError> {
+    if integration(deps.storage, cw721_address)? == Integration::Standard {
+        return Ok(Some(SubMsg::new(WasmMsg::Execute {
+            contract_addr: cw721_address.to_string(),
+            msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                recipient: recipient.to_string(),
+                token_id: token_id.to_string(),
+            })?,
+            funds: vec![],
+        })));
+    }
+
+    let contract = Cw721Contract {
+        address: cw721_address.clone(),
+    };
+    contract
+        .transfer_nft(recipient.to_string(), token_id.to_string())
+        .map_err(|e| ContractError::Cw721(Box::new(e)))?;
+    Ok(None)
+}
+
+fn integration(storage: &dyn Storage, cw721_address: &Addr) -> StdResult<Integration> {
+    Ok(INTEGRATIONS
+        .may_load(storage, cw721_address)?
+        .unwrap_or(Integration::DynamicLink))
+}
+
+// Returns whether the highest bid reaches the reserve price. Auctions without a reserve price
+// always meet it.
+fn is_reserve_met(state: &State, bid: &Bid) -> bool {
+    state.reserve_price.map_or(true, |reserve| {
+        bid.bidder.is_some() && bid.highest_bid >= reserve
+    })
+}
+
+fn auction_status(state: &State, time: Timestamp) -> AuctionStatus {
+    match state.mode {
+        Mode::End => AuctionStatus::Settled,
+        Mode::Canceled => AuctionStatus::Canceled,
+        _ if time < state.start_time => AuctionStatus::Scheduled,
+        _ if time <= closing_time(state) => AuctionStatus::Active,
+        _ => AuctionStatus::AwaitingSettlement,
+    }
+}
+
+// Returns the time after which the auction can be ended.
+fn closing_time(state: &State) -> Timestamp {
+    match state.auction_type {
+        AuctionType::SealedBid {
+            reveal_duration, ..
+        } => state.end_time.plus_seconds(reveal_duration),
+        _ => state.end_time,
+    }
+}
+
+// Hashes a sealed bid. Bidders commit this hash and reveal the bid and salt afterwards. There is
+// no query for it, since the bid and salt must not leave the bidder before the reveal.
+pub fn bid_commitment(auction_id: u64, bidder: &Addr, bid: Uint128, salt: &str) -> Binary {
+    let preimage = format!("{}:{}:{}:{}", auction_id, bidder, bid, salt);
+    Binary::from(Sha256::digest(preimage.as_bytes()).to_vec())
+}
+
+// Returns the price of the auction at `time`: the highest bid for english auctions,
+// and the decayed asking price for dutch auctions.
+fn current_price(state: &State, bid: &Bid, time: Timestamp) -> Uint128 {
+    if state.mode == Mode::End || state.mode == Mode::Canceled {
+        return bid.highest_bid;
+    }
+
+    match &state.auction_type {
+        AuctionType::English | AuctionType::SealedBid { .. } => bid.highest_bid,
+        AuctionType::Dutch {
+            start_price,
+            floor_price,
+            decay,
+        } => {
+            let elapsed = time.seconds().saturating_sub(state.start_time.seconds());
+            let drop = match decay {
+                PriceDecay::Linear {} => {
+                    let duration = state.end_time.seconds() - state.start_time.seconds();
+                    if elapsed >= duration {
+                        *start_price - *floor_price
+                    } else {
+                        (*start_price - *floor_price).multiply_ratio(elapsed, duration)
+                    }
+                }
+                PriceDecay::Stepwise { period, amount } => {
+                    amount.saturating_mul(Uint128::from(elapsed / period))
+                }
+            };
+            start_price.saturating_sub(drop).max(*floor_price)
+        }
+    }
+}
+
+fn load_admin_config(deps: Deps, info: &MessageInfo) -> Result<Config, ContractError> {
+    nonpayable(info).map_err(|_| ContractError::FundsError {})?;
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(config)
+}
+
+fn new_config(deps: Deps, admin: Addr, msg: InstantiateMsg) -> Result<Config, ContractError> {
+    let config = Config {
+        admin,
+        denoms: msg.denoms,
+        collections: msg.collections,
+        max_expiration: msg.max_expiration.unwrap_or(MAX_EXPIRATION_SECONDS),
+        min_duration: msg.min_duration.unwrap_or_default(),
+        fee_bps: msg.fee_bps,
+        fee_collector: msg.fee_collector,
+        min_increment: msg.min_increment,
+        paused: false,
+    };
+    validate_config(deps, &config)?;
+    for collection in config.collections.iter() {
+        deps.api.addr_validate(collection.as_str())?;
+    }
+    Ok(config)
+}
+
+fn validate_config(deps: Deps, config: &Config) -> Result<(), ContractError> {
+    deps.api.addr_validate(config.admin.as_str())?;
+    if config.denoms.is_empty() {
+        return Err(ContractError::EmptyDenomsError {});
+    }
+    for denom in config.denoms.iter() {
+        if let Denom::Cw20(address) = denom {
+            deps.api.addr_validate(address.as_str())?;
+        }
+    }
+    if config.min_duration > config.max_expiration {
+        return Err(ContractError::DurationConfigError {});
+    }
+    if config.fee_bps > BASIS_POINTS {
+        return Err(ContractError::FeeError { max: BASIS_POINTS });
+    }
+    deps.api.addr_validate(config.fee_collector.as_str())?;
+    if let Some(increment) = &config.min_increment {
+        validate_increment(increment)?;
+    }
+    Ok(())
+}
+
+fn validate_increment(increment: &BidIncrement) -> Result<(), ContractError> {
+    match increment {
+        BidIncrement::Absolute { amount } if amount.is_zero() => {
+            Err(ContractError::InvalidIncrementError {})
+        }
+        BidIncrement::Percentage { bps: 0 } => Err(ContractError::InvalidIncrementError {}),
+        _ => Ok(()),
+    }
+}
+
+// Returns the smallest bid accepted by the auction at `time`.
+// The first bid of an english auction only has to beat the start bid.
+fn min_next_bid(state: &State, bid: &Bid, time: Timestamp) -> Uint128 {
+    match state.auction_type {
+        AuctionType::English => match bid.bidder {
+            Some(_) => outbid(state, bid.highest_bid),
+            None => bid.highest_bid.saturating_add(Uint128::new(1)),
+        },
+        AuctionType::Dutch { .. } => current_price(state, bid, time),
+        AuctionType::SealedBid { .. } => state.start_bid,
+    }
+}
+
+// Returns the smallest bid which beats `amount` by the minimum increment.
+fn outbid(state: &State, amount: Uint128) -> Uint128 {
+    let increment = match &state.min_increment {
+        Some(BidIncrement::Absolute { amount }) => *amount,
+        Some(BidIncrement::Percentage { bps }) => {
+            // round up so that the increment is never below the percentage
+            let increment = amount.full_mul(*bps) + Uint256::from(BASIS_POINTS - 1);
+            Uint128::try_from(increment / Uint256::from(BASIS_POINTS)).unwrap_or(Uint128::MAX)
+        }
+        None => Uint128::zero(),
+    };
+    amount.saturating_add(increment.max(Uint128::new(1)))
+}
+
+// Returns the bid of a max bid up to `max` which has to reach `floor`. A max bid which covers
+// the reserve price bids at least the reserve price, so that the reserve is met.
+fn proxy_bid(state: &State, floor: Uint128, max: Uint128) -> Uint128 {
+    let bid = floor.min(max);
+    match state.reserve_price {
+        Some(reserve) if bid < reserve && reserve <= max => reserve,
+        _ => bid,
+    }
+}
+
+// Returns the funds escrowed for the highest bid, the whole max of a max bid.
+fn escrowed(storage: &dyn Storage, auction_id: u64, bid: &Bid) -> StdResult<Uint128> {
+    Ok(MAX_BIDS
+        .may_load(storage, auction_id)?
+        .unwrap_or(bid.highest_bid))
+}
+
+// Pays `amount` of `denom` held in escrow to `recipient`, or credits it to the balance of
+// `recipient` with `credit` since callable points cannot send messages.
+fn pay(
+    storage: &mut dyn Storage,
+    res: Response,
+    credit: bool,
+    denom: &Denom,
+    recipient: &Addr,
+    amount: Uint128,
+) -> StdResult<Response> {
+    if credit {
+        add_balance(storage, recipient, denom, amount)?;
+        Ok(res)
+    } else {
+        Ok(res.add_submessage(payout_msg(denom, recipient, amount)?))
+    }
+}
+
+// Pays `amount` of `denom` held in escrow to `recipient`.
+fn payout_msg(denom: &Denom, recipient: &Addr, amount: Uint128) -> StdResult<SubMsg> {
+    let msg: CosmosMsg = match denom {
+        Denom::Native(denom) => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        }
+        .into(),
+        Denom::Cw20(address) => WasmMsg::Execute {
+            contract_addr: address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    };
+    Ok(SubMsg::new(msg))
+}
+
+fn auction_type_label(auction_type: &AuctionType) -> &'static str {
+    match auction_type {
+        AuctionType::English => "english",
+        AuctionType::Dutch { .. } => "dutch",
+        AuctionType::SealedBid { .. } => "sealed_bid",
+    }
+}
+
+// Keys a balance by denom, the kind of the denom keeps native and cw20 balances apart.
+fn balance_key(denom: &Denom) -> String {
+    match denom {
+        Denom::Native(denom) => format!("native:{}", denom),
+        Denom::Cw20(address) => format!("cw20:{}", address),
+    }
+}
+
+fn balance_of(storage: &dyn Storage, owner: &Addr, denom: &Denom) -> StdResult<Uint128> {
+    Ok(BALANCES
+        .may_load(storage, (owner, &balance_key(denom)))?
+        .unwrap_or_default())
+}
+
+fn add_balance(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    denom: &Denom,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    BALANCES.update(storage, (owner, &balance_key(denom)), |balance| {
+        Ok(balance.unwrap_or_default() + amount)
+    })
+}
+
+fn sub_balance(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    denom: &Denom,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let key = balance_key(denom);
+    let balance = balance_of(storage, owner, denom)?;
+    if balance < amount {
+        return Err(ContractError::InsufficientBalanceError { balance, amount });
+    }
+    let balance = balance - amount;
+    if balance.is_zero() {
+        BALANCES.remove(storage, (owner, &key));
+    } else {
+        BALANCES.save(storage, (owner, &key), &balance)?;
+    }
+    Ok(balance)
+}
+
+fn integration_label(integration: &Integration) -> &'static str {
+    match integration {
+        Integration::DynamicLink => "dynamic_link",
+        Integration::DynamicLinkWithRoyalty => "dynamic_link_with_royalty",
+        Integration::Standard => "standard",
+    }
+}
+
+fn denom_label(denom: &Denom) -> String {
+    match denom {
+        Denom::Native(denom) => denom.clone(),
+        Denom::Cw20(address) => address.to_string(),
+    }
+}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetHighestBid {} => to_binary(&get_highest_bid(deps)?),
-        QueryMsg::GetAuctionItem {} => to_binary(&get_auction_item(deps)?),
+        QueryMsg::Config {} => to_binary(&get_config(deps)?),
+        QueryMsg::GetHighestBid { auction_id } => to_binary(&get_highest_bid(deps, auction_id)?),
+        QueryMsg::GetAuctionItem { auction_id } => {
+            to_binary(&get_auction_item(deps, env, auction_id)?)
+        }
         QueryMsg::GetAuctionHistory { idx } => to_binary(&get_auction_history(deps, idx)?),
+        QueryMsg::ListBids {
+            auction_id,
+            start_after,
+            limit,
+        } => to_binary(&list_bids(
+            bid_log()
+                .prefix(auction_id)
+                .range(
+                    deps.storage,
+                    start_after.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .map(move |item| item.map(|(seq, bid)| ((auction_id, seq), bid))),
+            limit,
+        )?),
+        QueryMsg::BidsByBidder {
+            bidder,
+            start_after,
+            limit,
+        } => to_binary(&list_bids(
+            bid_log().idx.bidder.prefix(bidder).range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            ),
+            limit,
+        )?),
+        QueryMsg::ListAuctionHistory { start_after, limit } => to_binary(&list_auction_history(
+            histories().range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            ),
+            limit,
+        )?),
+        QueryMsg::ListAuctionHistoryBySeller {
+            seller,
+            start_after,
+            limit,
+        } => to_binary(&list_auction_history(
+            histories().idx.seller.prefix(seller).range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            ),
+            limit,
+        )?),
+        QueryMsg::ListAuctionHistoryByBidder {
+            bidder,
+            start_after,
+            limit,
+        } => to_binary(&list_auction_history(
+            histories().idx.bidder.prefix(bidder).range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            ),
+            limit,
+        )?),
+        QueryMsg::ListAuctionHistoryByCollection {
+            cw721_address,
+            start_after,
+            limit,
+        } => to_binary(&list_auction_history(
+            histories().idx.cw721_address.prefix(cw721_address).range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            ),
+            limit,
+        )?),
+        QueryMsg::GetAuctionHistoryCount {} => to_binary(&AuctionHistoryCountResponse {
+            count: HISTORY_INDEX.load(deps.storage)?,
+        }),
+        QueryMsg::GetCurrentPrice { auction_id } => {
+            to_binary(&get_current_price(deps, env, auction_id)?)
+        }
+        QueryMsg::GetMinNextBid { auction_id } => {
+            to_binary(&get_min_next_bid(deps, env, auction_id)?)
+        }
+        QueryMsg::GetIntegration { cw721_address } => to_binary(&IntegrationResponse {
+            integration: integration(deps.storage, &cw721_address)?,
+        }),
+        QueryMsg::GetBalance { address, denom } => to_binary(&BalanceResponse {
+            amount: balance_of(deps.storage, &address, &denom)?,
+            denom,
+        }),
+    }
+}
+
+// Callable points for other contracts, see `crate::interface::Auction`.
+#[cfg(not(feature = "library"))]
+#[callable_points]
+mod callable_points {
+    use super::*;
+
+    #[callable_point]
+    fn highest_bid(deps: Deps, _env: Env, auction_id: u64) -> StdResult<HighestBidResponse> {
+        get_highest_bid(deps, auction_id)
+    }
+
+    #[callable_point]
+    fn auction_item(deps: Deps, env: Env, auction_id: u64) -> StdResult<AuctionItemResponse> {
+        get_auction_item(deps, env, auction_id)
+    }
+
+    // Bids for the calling contract from its balance. Refunds and payouts cannot be sent from a
+    // callable point, so they are credited to the balances of their recipients.
+    #[callable_point]
+    fn place_bid_for(mut deps: DepsMut, env: Env, msg: PlaceBidMsg) -> Result<(), ContractError> {
+        let bidder = deps.api.get_caller_addr()?;
+        if CONFIG.load(deps.storage)?.paused {
+            return Err(ContractError::PausedError {});
+        }
+
+        // the balance is checked before anything is written, and only debited once the bid is
+        // placed. refunds credited to the caller by the bid only raise it.
+        let denom = AUCTIONS.load(deps.storage, msg.auction_id)?.denom;
+        let amount = msg.bid;
+        let balance = balance_of(deps.storage, &bidder, &denom)?;
+        if balance < amount {
+            return Err(ContractError::InsufficientBalanceError { balance, amount });
+        }
+        execute_place_bid(
+            deps.branch(),
+            env,
+            bidder.clone(),
+            denom.clone(),
+            amount,
+            BidOrder::Bid(msg),
+            true,
+        )?;
+        sub_balance(deps.storage, &bidder, &denom, amount)?;
+        Ok(())
+    }
+
+    #[callable_point]
+    fn is_settled(deps: Deps, env: Env, auction_id: u64) -> StdResult<bool> {
+        let state = AUCTIONS.load(deps.storage, auction_id)?;
+        Ok(auction_status(&state, env.block.time) == AuctionStatus::Settled)
     }
 }
 
-fn get_highest_bid(deps: Deps) -> StdResult<HighestBidResponse> {
-    let bid = BID.load(deps.storage)?;
+fn get_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        admin: config.admin,
+        denoms: config.denoms,
+        collections: config.collections,
+        max_expiration: config.max_expiration,
+        min_duration: config.min_duration,
+        fee_bps: config.fee_bps,
+        fee_collector: config.fee_collector,
+        min_increment: config.min_increment,
+        paused: config.paused,
+    })
+}
+
+fn get_highest_bid(deps: Deps, auction_id: u64) -> StdResult<HighestBidResponse> {
+    let state = AUCTIONS.load(deps.storage, auction_id)?;
+    let bid = BIDS.load(deps.storage, auction_id)?;
     Ok(HighestBidResponse {
         highest_bid: bid.highest_bid,
+        denom: state.denom,
         bidder: bid.bidder,
     })
 }
 
-fn get_auction_item(deps: Deps) -> StdResult<AuctionItemResponse> {
-    let state = STATE.load(deps.storage)?;
+fn get_auction_item(deps: Deps, env: Env, auction_id: u64) -> StdResult<AuctionItemResponse> {
+    let state = AUCTIONS.load(deps.storage, auction_id)?;
+    let bid = BIDS.load(deps.storage, auction_id)?;
+    let reserve_met = is_reserve_met(&state, &bid);
     Ok(AuctionItemResponse {
+        status: auction_status(&state, env.block.time),
+        start_time: state.start_time,
         end_time: state.end_time,
         cw721_address: state.cw721_address,
         token_id: state.token_id,
+        bundle: state.bundle,
+        auction_type: state.auction_type,
         start_bid: state.start_bid,
+        reserve_met,
+        buy_now_price: state.buy_now_price,
+        time_extension: state.time_extension,
+        min_increment: state.min_increment,
+        denom: state.denom,
+    })
+}
+
+fn get_current_price(deps: Deps, env: Env, auction_id: u64) -> StdResult<CurrentPriceResponse> {
+    let state = AUCTIONS.load(deps.storage, auction_id)?;
+    let bid = BIDS.load(deps.storage, auction_id)?;
+    Ok(CurrentPriceResponse {
+        price: current_price(&state, &bid, env.block.time),
+        denom: state.denom,
+    })
+}
+
+fn get_min_next_bid(deps: Deps, env: Env, auction_id: u64) -> StdResult<MinNextBidResponse> {
+    let state = AUCTIONS.load(deps.storage, auction_id)?;
+    let bid = BIDS.load(deps.storage, auction_id)?;
+    Ok(MinNextBidResponse {
+        min_bid: min_next_bid(&state, &bid, env.block.time),
+        denom: state.denom,
     })
 }
 
 fn get_auction_history(deps: Deps, idx: u32) -> StdResult<AuctionHistoryResponse> {
-    let history = HISTORIES.load(deps.storage, idx)?;
-    Ok(AuctionHistoryResponse {
+    let history = histories().load(deps.storage, idx)?;
+    Ok(history_response(idx, history))
+}
+
+fn list_auction_history(
+    histories: Box<dyn Iterator<Item = StdResult<(u32, History)>> + '_>,
+    limit: Option<u32>,
+) -> StdResult<AuctionHistoriesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let histories = histories
+        .take(limit)
+        .map(|item| item.map(|(idx, history)| history_response(idx, history)))
+        .collect::<StdResult<_>>()?;
+    Ok(AuctionHistoriesResponse { histories })
+}
+
+fn list_bids(
+    bids: impl Iterator<Item = StdResult<((u64, u32), BidRecord)>>,
+    limit: Option<u32>,
+) -> StdResult<BidsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let bids = bids
+        .take(limit)
+        .map(|item| {
+            item.map(|((auction_id, seq), bid)| BidResponse {
+                auction_id,
+                seq,
+                bidder: bid.bidder,
+                amount: bid.amount,
+                time: bid.time,
+                height: bid.height,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(BidsResponse { bids })
+}
+
+fn history_response(idx: u32, history: History) -> AuctionHistoryResponse {
+    AuctionHistoryResponse {
+        idx,
+        auction_id: history.auction_id,
+        bid_count: history.bid_count,
         end_time: history.end_time,
         seller: history.seller,
         cw721_address: history.cw721_address,
         token_id: history.token_id,
+        bundle: history.bundle,
         highest_bid: history.highest_bid,
+        denom: history.denom,
         bidder: history.bidder,
-    })
+        fee: history.fee,
+        fee_collector: history.fee_collector,
+        royalty: history.royalty,
+        royalties: history.royalties,
+        seller_amount: history.seller_amount,
+    }
 }
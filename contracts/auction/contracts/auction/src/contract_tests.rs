@@ -0,0 +1,502 @@
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    coins, from_binary, to_binary, Addr, BankMsg, ContractResult, CosmosMsg, Env, OwnedDeps,
+    Response, SubMsg, SystemResult, Timestamp, Uint128, WasmMsg, WasmQuery,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::Denom;
+use cw721::{Approval, ApprovalResponse, Cw721ExecuteMsg, Cw721QueryMsg, OwnerOfResponse};
+use cw_utils::Expiration;
+
+use crate::contract::{execute, instantiate, migrate, query};
+use crate::msg::{
+    AuctionHistoriesResponse, AuctionHistoryResponse, BidsResponse, ConfigResponse, ExecuteMsg,
+    HighestBidResponse, InstantiateMsg, MigrateMsg, PlaceBidMsg, PlaceMaxBidMsg, QueryMsg,
+    StartAuctionMsg, UpdateConfigMsg,
+};
+use crate::state::{
+    AuctionType, Integration, LegacyBid, LegacyHistory, LegacyState, Mode, AUCTION_INDEX,
+    HISTORY_INDEX, INTEGRATIONS, LEGACY_BID, LEGACY_HISTORIES, LEGACY_STATE,
+};
+use crate::ContractError;
+
+const ADMIN: &str = "admin";
+const SELLER: &str = "seller";
+const COLLECTION: &str = "collection";
+const TOKEN_ID: &str = "token";
+const DENOM: &str = "cony";
+
+type Deps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+// Starts an english auction of a standard collection, which is checked with queries and moved
+// with messages, so that no dynamic link is called.
+fn setup() -> (Deps, u64) {
+    let mut deps = mock_dependencies();
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { msg, .. } => {
+            let response = match from_binary(msg).unwrap() {
+                Cw721QueryMsg::OwnerOf { .. } => to_binary(&OwnerOfResponse {
+                    owner: SELLER.to_string(),
+                    approvals: vec![],
+                }),
+                Cw721QueryMsg::Approval { .. } => to_binary(&ApprovalResponse {
+                    approval: Approval {
+                        spender: MOCK_CONTRACT_ADDR.to_string(),
+                        expires: Expiration::Never {},
+                    },
+                }),
+                query => panic!("unexpected query: {:?}", query),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        }
+        query => panic!("unexpected query: {:?}", query),
+    });
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        instantiate_msg(None),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::AddCollection {
+            cw721_address: Addr::unchecked(COLLECTION),
+            integration: Some(Integration::Standard),
+        },
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SELLER, &[]),
+        ExecuteMsg::StartAuction(StartAuctionMsg {
+            expiration_time: 100,
+            cw721_address: Addr::unchecked(COLLECTION),
+            token_id: TOKEN_ID.to_string(),
+            bundle: vec![],
+            start_time: None,
+            auction_type: AuctionType::English,
+            start_bid: Uint128::new(10),
+            reserve_price: None,
+            buy_now_price: None,
+            time_extension: None,
+            min_increment: None,
+            denom: Denom::Native(DENOM.to_string()),
+        }),
+    )
+    .unwrap();
+    (deps, attribute(&res, "auction_id").parse().unwrap())
+}
+
+fn instantiate_msg(admin: Option<Addr>) -> InstantiateMsg {
+    InstantiateMsg {
+        admin,
+        denoms: vec![Denom::Native(DENOM.to_string())],
+        collections: vec![],
+        max_expiration: None,
+        min_duration: None,
+        fee_bps: 0,
+        fee_collector: Addr::unchecked("fees"),
+        min_increment: None,
+    }
+}
+
+fn place_bid(deps: &mut Deps, bidder: &str, auction_id: u64, bid: u128) -> Response {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(bidder, &coins(bid, DENOM)),
+        ExecuteMsg::PlaceBid(PlaceBidMsg {
+            auction_id,
+            bid: Uint128::new(bid),
+        }),
+    )
+    .unwrap()
+}
+
+fn place_max_bid(
+    deps: &mut Deps,
+    bidder: &str,
+    auction_id: u64,
+    max_bid: u128,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(bidder, &coins(max_bid, DENOM)),
+        ExecuteMsg::PlaceMaxBid(PlaceMaxBidMsg {
+            auction_id,
+            max_bid: Uint128::new(max_bid),
+        }),
+    )
+}
+
+fn end_auction(deps: &mut Deps, auction_id: u64) -> Response {
+    execute(
+        deps.as_mut(),
+        ended_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::EndAuction { auction_id },
+    )
+    .unwrap()
+}
+
+fn ended_env() -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(101);
+    env
+}
+
+fn highest_bid(deps: &Deps, auction_id: u64) -> HighestBidResponse {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetHighestBid { auction_id },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+fn attribute(res: &Response, key: &str) -> String {
+    res.attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+        .unwrap()
+}
+
+fn send(recipient: &str, amount: u128) -> SubMsg {
+    SubMsg::new(BankMsg::Send {
+        to_address: recipient.to_string(),
+        amount: coins(amount, DENOM),
+    })
+}
+
+fn transfer_nft(recipient: &str) -> SubMsg {
+    SubMsg::new(CosmosMsg::from(WasmMsg::Execute {
+        contract_addr: COLLECTION.to_string(),
+        msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: TOKEN_ID.to_string(),
+        })
+        .unwrap(),
+        funds: vec![],
+    }))
+}
+
+#[test]
+fn max_bid_leads_just_above_other_bids() {
+    let (mut deps, auction_id) = setup();
+
+    let res = place_max_bid(&mut deps, "alice", auction_id, 100).unwrap();
+    assert!(res.messages.is_empty());
+    let bid = highest_bid(&deps, auction_id);
+    assert_eq!(bid.highest_bid, Uint128::new(11));
+    assert_eq!(bid.bidder, Some(Addr::unchecked("alice")));
+
+    // the max covers the bid, so it is refunded and alice keeps the lead
+    let res = place_bid(&mut deps, "bob", auction_id, 50);
+    assert_eq!(res.messages, vec![send("bob", 50)]);
+    let bid = highest_bid(&deps, auction_id);
+    assert_eq!(bid.highest_bid, Uint128::new(51));
+    assert_eq!(bid.bidder, Some(Addr::unchecked("alice")));
+
+    // the seller is paid the price and the rest of the max is refunded
+    let res = end_auction(&mut deps, auction_id);
+    assert_eq!(
+        res.messages,
+        vec![send(SELLER, 51), send("alice", 49), transfer_nft("alice")]
+    );
+}
+
+#[test]
+fn max_bid_outbid_refunds_the_whole_max() {
+    let (mut deps, auction_id) = setup();
+
+    place_max_bid(&mut deps, "alice", auction_id, 100).unwrap();
+    let res = place_max_bid(&mut deps, "bob", auction_id, 200).unwrap();
+    assert_eq!(res.messages, vec![send("alice", 100)]);
+    let bid = highest_bid(&deps, auction_id);
+    assert_eq!(bid.highest_bid, Uint128::new(101));
+    assert_eq!(bid.bidder, Some(Addr::unchecked("bob")));
+
+    let res = end_auction(&mut deps, auction_id);
+    assert_eq!(
+        res.messages,
+        vec![send(SELLER, 101), send("bob", 99), transfer_nft("bob")]
+    );
+}
+
+#[test]
+fn max_bid_reached_settles_without_refund() {
+    let (mut deps, auction_id) = setup();
+
+    // the earlier max bid wins the tie at its max
+    place_max_bid(&mut deps, "alice", auction_id, 100).unwrap();
+    let res = place_bid(&mut deps, "bob", auction_id, 100);
+    assert_eq!(res.messages, vec![send("bob", 100)]);
+    let bid = highest_bid(&deps, auction_id);
+    assert_eq!(bid.highest_bid, Uint128::new(100));
+    assert_eq!(bid.bidder, Some(Addr::unchecked("alice")));
+
+    // nothing is left of the max, and no empty refund is sent
+    let res = end_auction(&mut deps, auction_id);
+    assert_eq!(res.messages, vec![send(SELLER, 100), transfer_nft("alice")]);
+}
+
+#[test]
+fn running_auction_keeps_its_fee() {
+    let (mut deps, auction_id) = setup();
+    place_bid(&mut deps, "alice", auction_id, 100);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            denoms: None,
+            max_expiration: None,
+            min_duration: None,
+            fee_bps: Some(10000),
+            fee_collector: Some(Addr::unchecked("other")),
+            min_increment: None,
+        }),
+    )
+    .unwrap();
+
+    let res = end_auction(&mut deps, auction_id);
+    assert_eq!(res.messages, vec![send(SELLER, 100), transfer_nft("alice")]);
+    assert_eq!(attribute(&res, "fee_collector"), "fees");
+}
+
+#[test]
+fn max_bid_replaced_by_its_bidder() {
+    let (mut deps, auction_id) = setup();
+
+    place_max_bid(&mut deps, "alice", auction_id, 100).unwrap();
+    place_bid(&mut deps, "bob", auction_id, 50);
+
+    // bob was refunded against the max, so it cannot be lowered
+    let err = place_max_bid(&mut deps, "alice", auction_id, 80).unwrap_err();
+    assert!(matches!(err, ContractError::MaxBidError { .. }));
+
+    // a higher max keeps the price, refunds the old max and is not logged again
+    let res = place_max_bid(&mut deps, "alice", auction_id, 150).unwrap();
+    assert_eq!(res.messages, vec![send("alice", 100)]);
+    let bid = highest_bid(&deps, auction_id);
+    assert_eq!(bid.highest_bid, Uint128::new(51));
+    assert_eq!(bid.bidder, Some(Addr::unchecked("alice")));
+
+    let bids: BidsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ListBids {
+                auction_id,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let amounts: Vec<_> = bids.bids.iter().map(|bid| bid.amount.u128()).collect();
+    assert_eq!(amounts, vec![11, 50, 51]);
+}
+
+// Writes the layout of 0.1.0 with two finished auctions and the single auction in `mode`.
+fn legacy_setup(mode: Mode, end_time: Timestamp) -> Deps {
+    let mut deps = mock_dependencies();
+    let storage = deps.as_mut().storage;
+    set_contract_version(storage, "fnsa-contracts:auction", "0.1.0").unwrap();
+    LEGACY_STATE
+        .save(
+            storage,
+            &LegacyState {
+                mode,
+                end_time,
+                seller: Addr::unchecked(SELLER),
+                cw721_address: Addr::unchecked(COLLECTION),
+                token_id: TOKEN_ID.to_string(),
+                start_bid: 10,
+            },
+        )
+        .unwrap();
+    LEGACY_BID
+        .save(
+            storage,
+            &LegacyBid {
+                highest_bid: 20,
+                bidder: Addr::unchecked("alice"),
+            },
+        )
+        .unwrap();
+    let histories = [
+        (SELLER, "alice", COLLECTION, 30),
+        ("carol", "bob", "other", 40),
+    ];
+    for (idx, (seller, bidder, collection, highest_bid)) in histories.into_iter().enumerate() {
+        LEGACY_HISTORIES
+            .save(
+                storage,
+                idx as u32,
+                &LegacyHistory {
+                    end_time: Timestamp::from_seconds(1000 + idx as u64),
+                    seller: Addr::unchecked(seller),
+                    cw721_address: Addr::unchecked(collection),
+                    token_id: format!("token{}", idx),
+                    highest_bid,
+                    bidder: Addr::unchecked(bidder),
+                },
+            )
+            .unwrap();
+    }
+    HISTORY_INDEX.save(storage, &2).unwrap();
+    deps
+}
+
+fn migrate_msg() -> MigrateMsg {
+    MigrateMsg {
+        config: Some(instantiate_msg(Some(Addr::unchecked(ADMIN)))),
+    }
+}
+
+fn list_histories(deps: &Deps, msg: QueryMsg) -> Vec<AuctionHistoryResponse> {
+    let res: AuctionHistoriesResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    res.histories
+}
+
+#[test]
+fn migrate_moves_legacy_histories() {
+    let mut deps = legacy_setup(Mode::End, Timestamp::from_seconds(1000));
+
+    let res = migrate(deps.as_mut(), mock_env(), migrate_msg()).unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(attribute(&res, "migrated_histories"), "2");
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.admin, Addr::unchecked(ADMIN));
+    assert_eq!(config.denoms, vec![Denom::Native(DENOM.to_string())]);
+
+    // each history gets its own auction id, and new auctions come after them
+    assert_eq!(AUCTION_INDEX.load(deps.as_ref().storage).unwrap(), 2);
+    assert!(LEGACY_STATE
+        .may_load(deps.as_ref().storage)
+        .unwrap()
+        .is_none());
+    assert!(LEGACY_BID
+        .may_load(deps.as_ref().storage)
+        .unwrap()
+        .is_none());
+
+    let history: AuctionHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetAuctionHistory { idx: 1 },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(history.auction_id, 1);
+    assert_eq!(history.seller, Addr::unchecked("carol"));
+    assert_eq!(history.bidder, Addr::unchecked("bob"));
+    assert_eq!(history.cw721_address, Addr::unchecked("other"));
+    assert_eq!(history.token_id, "token1");
+    assert_eq!(history.highest_bid, Uint128::new(40));
+    assert_eq!(history.denom, Denom::Native(DENOM.to_string()));
+    assert_eq!(history.fee, Uint128::zero());
+    assert_eq!(history.seller_amount, Uint128::new(40));
+
+    // the indexes are built for the moved histories
+    let by_seller = list_histories(
+        &deps,
+        QueryMsg::ListAuctionHistoryBySeller {
+            seller: Addr::unchecked(SELLER),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(by_seller.len(), 1);
+    assert_eq!(by_seller[0].idx, 0);
+    let by_bidder = list_histories(
+        &deps,
+        QueryMsg::ListAuctionHistoryByBidder {
+            bidder: Addr::unchecked("bob"),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(by_bidder.len(), 1);
+    assert_eq!(by_bidder[0].idx, 1);
+    let by_collection = list_histories(
+        &deps,
+        QueryMsg::ListAuctionHistoryByCollection {
+            cw721_address: Addr::unchecked(COLLECTION),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(by_collection.len(), 1);
+    assert_eq!(by_collection[0].token_id, "token0");
+}
+
+#[test]
+fn migrate_returns_nft_of_expired_legacy_auction() {
+    let now = mock_env().block.time;
+
+    // the legacy auction is still running
+    let mut deps = legacy_setup(Mode::Progress, now);
+    let err = migrate(deps.as_mut(), mock_env(), migrate_msg()).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::LegacyAuctionInProgressError {}
+    ));
+
+    // the winner walked away from the expired auction. the collection is marked as standard, so
+    // that the NFT is returned with a message instead of the dynamic link
+    let mut deps = legacy_setup(Mode::Progress, now.minus_seconds(1));
+    INTEGRATIONS
+        .save(
+            deps.as_mut().storage,
+            &Addr::unchecked(COLLECTION),
+            &Integration::Standard,
+        )
+        .unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), migrate_msg()).unwrap();
+    assert_eq!(res.messages, vec![transfer_nft(SELLER)]);
+    assert_eq!(attribute(&res, "returned_token_id"), TOKEN_ID);
+    assert_eq!(attribute(&res, "migrated_histories"), "2");
+}
+
+#[test]
+fn migrate_rejects_other_contracts_and_downgrades() {
+    let mut deps = legacy_setup(Mode::End, Timestamp::from_seconds(1000));
+    set_contract_version(deps.as_mut().storage, "crates.io:other", "0.1.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), migrate_msg()).unwrap_err();
+    assert!(matches!(err, ContractError::CannotMigrateError { .. }));
+
+    set_contract_version(deps.as_mut().storage, "fnsa-contracts:auction", "99.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), migrate_msg()).unwrap_err();
+    assert!(matches!(err, ContractError::DowngradeError { .. }));
+
+    // 0.1.0 has no config, so it must be given
+    set_contract_version(deps.as_mut().storage, "fnsa-contracts:auction", "0.1.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { config: None }).unwrap_err();
+    assert!(matches!(err, ContractError::MigrateConfigError {}));
+}
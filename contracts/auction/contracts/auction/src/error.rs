@@ -1,7 +1,8 @@
-use cosmwasm_std::{Addr, StdError};
+use cosmwasm_std::{Addr, StdError, Uint128};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Deserialize, Serialize)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
@@ -17,27 +18,140 @@ pub enum ContractError {
     #[error("expiration time is too long: {val:?}")]
     ExpirationTimeError { val: u64 },
 
-    #[error("transfer NFT is failed: sender:{sender:?}, token_id:{token_id:?}")]
-    TransferNFTError { sender: Addr, token_id: String },
+    #[error("expiration time is too short: {val:?}")]
+    DurationError { val: u64 },
 
-    #[error("another auction is progress")]
-    AuctionProgressError {},
+    #[error("Error in cw721: {0}")]
+    Cw721(Box<TransferError>),
 
     #[error("auction is not progress")]
     AuctionNoProgressError {},
 
     #[error("bid is less than the highest bid: bid:{bid:?}, highest bid:{highest_bid:?}")]
-    InvalidBidError { bid: u64, highest_bid: u64 },
+    InvalidBidError { bid: Uint128, highest_bid: Uint128 },
 
     #[error("not yet expiration time for the auction to end")]
     AuctionTimeError {},
 
-    #[error("insufficient balance")]
-    InsufficientBalanceError {},
-
     #[error("invalid funds")]
     FundsError {},
 
     #[error("NFT is not approved to this contract")]
     ApprovalError {},
+
+    #[error("denom is not accepted by this contract: {denom:?}")]
+    InvalidDenomError { denom: String },
+
+    #[error("at least one denom must be accepted")]
+    EmptyDenomsError {},
+
+    #[error("invalid price decay: floor price is above start price or period is zero")]
+    PriceDecayError {},
+
+    #[error("this operation is not supported by the auction type")]
+    AuctionTypeError {},
+
+    #[error("buy-now price must be above the start bid and the reserve price")]
+    BuyNowPriceError {},
+
+    #[error("auction already has bids")]
+    AuctionHasBidsError {},
+
+    #[error("reveal duration must not be zero")]
+    RevealDurationError {},
+
+    #[error("commitment must be a 32-byte SHA-256 hash")]
+    InvalidCommitmentError {},
+
+    #[error("bid is already committed")]
+    AlreadyCommittedError {},
+
+    #[error("no committed bid to reveal")]
+    NoCommitmentError {},
+
+    #[error("revealed bid does not match the commitment")]
+    CommitmentMismatchError {},
+
+    #[error("bid exceeds the deposit: bid:{bid:?}, deposit:{deposit:?}")]
+    DepositError { bid: Uint128, deposit: Uint128 },
+
+    #[error("fee must not exceed {max:?} basis points")]
+    FeeError { max: u64 },
+
+    #[error("minimum increment must not be zero")]
+    InvalidIncrementError {},
+
+    #[error("bid is below the minimum next bid: bid:{bid:?}, min_bid:{min_bid:?}")]
+    BidIncrementError { bid: Uint128, min_bid: Uint128 },
+
+    #[error("bid is below the max bid of the highest bidder: bid:{bid:?}, max_bid:{max_bid:?}")]
+    MaxBidError { bid: Uint128, max_bid: Uint128 },
+
+    #[error("collection is not allowed: {cw721_address:?}")]
+    CollectionNotAllowedError { cw721_address: Addr },
+
+    #[error("collection is already allowed: {cw721_address:?}")]
+    DuplicateCollectionError { cw721_address: Addr },
+
+    #[error("minimum duration is above the maximum expiration time")]
+    DurationConfigError {},
+
+    #[error("contract is paused")]
+    PausedError {},
+
+    #[error("contract is not paused")]
+    NotPausedError {},
+
+    #[error("cannot migrate from a different contract: {previous_contract:?}")]
+    CannotMigrateError { previous_contract: String },
+
+    #[error("cannot migrate from {stored:?} to the older {current:?}")]
+    DowngradeError { stored: String, current: String },
+
+    #[error("invalid contract version: {version:?}")]
+    VersionError { version: String },
+
+    #[error("config with an admin is required to migrate from the single auction layout")]
+    MigrateConfigError {},
+
+    #[error("auction of the single auction layout is not expired yet")]
+    LegacyAuctionInProgressError {},
+
+    #[error("bundle has more than {max:?} nfts")]
+    BundleSizeError { max: usize },
+
+    #[error("nft is included more than once: {token_id:?}")]
+    DuplicateNftError { token_id: String },
+
+    #[error("start time must not be in the past or further than the max expiration time")]
+    StartTimeError {},
+
+    #[error("auction is not started yet")]
+    AuctionNotStartedError {},
+
+    #[error("nfts of standard cw721 collections cannot be transferred from a callable point")]
+    CallableTransferError {},
+
+    #[error("balance is not enough: balance:{balance:?}, amount:{amount:?}")]
+    InsufficientBalanceError { balance: Uint128, amount: Uint128 },
+}
+
+// Error of the `transfer_nft` callable point of cw721-base-dynamiclink,
+// which is deserialized from the same variants.
+#[derive(Error, Debug, PartialEq, Deserialize, Serialize)]
+pub enum TransferError {
+    #[error("{msg}")]
+    Std { msg: String },
+
+    #[error("caller is neither the owner nor approved")]
+    Unauthorized {},
+
+    #[error("token not found")]
+    NotFound {},
+
+    #[error("approval of the caller is expired")]
+    Expired {},
+
+    #[error("Approval not found for: {spender}")]
+    ApprovalNotFound { spender: String },
 }
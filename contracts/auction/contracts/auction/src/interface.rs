@@ -0,0 +1,20 @@
+use cosmwasm_std::{dynamic_link, Addr, Contract, StdResult};
+
+use crate::error::ContractError;
+use crate::msg::{AuctionItemResponse, HighestBidResponse, PlaceBidMsg};
+
+// An auction contract to be called by other contracts through dynamic link.
+#[derive(Contract)]
+pub struct AuctionContract {
+    pub address: Addr,
+}
+
+// The callable points of the auction contract.
+// `place_bid_for` bids for the calling contract from its balance, see `Deposit`.
+#[dynamic_link(AuctionContract)]
+pub trait Auction: Contract {
+    fn highest_bid(&self, auction_id: u64) -> StdResult<HighestBidResponse>;
+    fn auction_item(&self, auction_id: u64) -> StdResult<AuctionItemResponse>;
+    fn place_bid_for(&self, msg: PlaceBidMsg) -> Result<(), ContractError>;
+    fn is_settled(&self, auction_id: u64) -> StdResult<bool>;
+}
@@ -1,6 +1,9 @@
 pub mod contract;
+#[cfg(test)]
+mod contract_tests;
 mod error;
+pub mod interface;
 pub mod msg;
 pub mod state;
 
-pub use crate::error::ContractError;
+pub use crate::error::{ContractError, TransferError};
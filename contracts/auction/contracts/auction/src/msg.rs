@@ -1,14 +1,93 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Timestamp};
+use cosmwasm_std::{Addr, Binary, Timestamp, Uint128};
+use cw20::{Cw20ReceiveMsg, Denom};
+use cw721::Cw721ReceiveMsg;
+
+use crate::state::{AuctionType, BidIncrement, Integration, Nft, Royalty, TimeExtension};
 
 #[cw_serde]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    // defaults to the sender
+    pub admin: Option<Addr>,
+    pub denoms: Vec<Denom>,
+    pub collections: Vec<Addr>,
+    // defaults to a week
+    pub max_expiration: Option<u64>,
+    pub min_duration: Option<u64>,
+    // platform fee in basis points of the final price
+    pub fee_bps: u64,
+    pub fee_collector: Addr,
+    pub min_increment: Option<BidIncrement>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    // required to migrate from 0.1.0, which has no config, and `admin` must be set
+    pub config: Option<InstantiateMsg>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg {
     StartAuction(StartAuctionMsg),
     PlaceBid(PlaceBidMsg),
-    EndAuction {},
+    // bids for the sender just enough to lead, up to `max_bid`
+    PlaceMaxBid(PlaceMaxBidMsg),
+    CommitBid(CommitBidMsg),
+    RevealBid(RevealBidMsg),
+    CancelAuction {
+        auction_id: u64,
+    },
+    EndAuction {
+        auction_id: u64,
+    },
+    Receive(Cw20ReceiveMsg),
+    // starts the auction of the sent nft with the embedded `StartAuctionMsg`
+    ReceiveNft(Cw721ReceiveMsg),
+    UpdateConfig(UpdateConfigMsg),
+    // `integration` defaults to the dynamic link
+    AddCollection {
+        cw721_address: Addr,
+        integration: Option<Integration>,
+    },
+    RemoveCollection {
+        cw721_address: Addr,
+    },
+    TransferAdmin {
+        admin: Addr,
+    },
+    Pause {},
+    Unpause {},
+    // scans up to `limit` auctions after `start_after`
+    EmergencyWithdraw {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // funds the balance of the sender for the `place_bid_for` callable point
+    Deposit {},
+    // withdraws `amount`, or all, of the balance in `denom`
+    Withdraw {
+        denom: Denom,
+        amount: Option<Uint128>,
+    },
+}
+
+// only the given settings are updated
+#[cw_serde]
+pub struct UpdateConfigMsg {
+    pub denoms: Option<Vec<Denom>>,
+    pub max_expiration: Option<u64>,
+    pub min_duration: Option<u64>,
+    pub fee_bps: Option<u64>,
+    pub fee_collector: Option<Addr>,
+    pub min_increment: Option<BidIncrement>,
+}
+
+#[cw_serde]
+pub enum ReceiveMsg {
+    PlaceBid(PlaceBidMsg),
+    PlaceMaxBid(PlaceMaxBidMsg),
+    CommitBid(CommitBidMsg),
+    Deposit {},
 }
 
 #[cw_serde]
@@ -16,41 +95,218 @@ pub struct StartAuctionMsg {
     pub expiration_time: u64,
     pub cw721_address: Addr,
     pub token_id: String,
-    pub start_bid: u64,
+    // more nfts sold in the same lot
+    #[serde(default)]
+    pub bundle: Vec<Nft>,
+    // bidding opens at this time, or right away without it
+    pub start_time: Option<Timestamp>,
+    pub auction_type: AuctionType,
+    pub start_bid: Uint128,
+    pub reserve_price: Option<Uint128>,
+    pub buy_now_price: Option<Uint128>,
+    pub time_extension: Option<TimeExtension>,
+    // overrides the default minimum increment of the contract
+    pub min_increment: Option<BidIncrement>,
+    pub denom: Denom,
 }
 
 #[cw_serde]
 pub struct PlaceBidMsg {
-    pub bid: u64,
+    pub auction_id: u64,
+    pub bid: Uint128,
+}
+
+#[cw_serde]
+pub struct PlaceMaxBidMsg {
+    pub auction_id: u64,
+    pub max_bid: Uint128,
+}
+
+#[cw_serde]
+pub struct CommitBidMsg {
+    pub auction_id: u64,
+    pub commitment: Binary,
+}
+
+#[cw_serde]
+pub struct RevealBidMsg {
+    pub auction_id: u64,
+    pub bid: Uint128,
+    pub salt: String,
 }
 
 #[cw_serde]
 pub enum QueryMsg {
-    GetHighestBid {},
-    GetAuctionItem {},
-    GetAuctionHistory { idx: u32 },
+    Config {},
+    GetHighestBid {
+        auction_id: u64,
+    },
+    GetAuctionItem {
+        auction_id: u64,
+    },
+    GetAuctionHistory {
+        idx: u32,
+    },
+    ListBids {
+        auction_id: u64,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    BidsByBidder {
+        bidder: Addr,
+        // (auction_id, seq) of the last bid of the previous page
+        start_after: Option<(u64, u32)>,
+        limit: Option<u32>,
+    },
+    ListAuctionHistory {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    ListAuctionHistoryBySeller {
+        seller: Addr,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    ListAuctionHistoryByBidder {
+        bidder: Addr,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    ListAuctionHistoryByCollection {
+        cw721_address: Addr,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    GetAuctionHistoryCount {},
+    GetCurrentPrice {
+        auction_id: u64,
+    },
+    GetMinNextBid {
+        auction_id: u64,
+    },
+    GetBalance {
+        address: Addr,
+        denom: Denom,
+    },
+    GetIntegration {
+        cw721_address: Addr,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub admin: Addr,
+    pub denoms: Vec<Denom>,
+    pub collections: Vec<Addr>,
+    pub max_expiration: u64,
+    pub min_duration: u64,
+    pub fee_bps: u64,
+    pub fee_collector: Addr,
+    pub min_increment: Option<BidIncrement>,
+    pub paused: bool,
 }
 
 #[cw_serde]
 pub struct HighestBidResponse {
-    pub highest_bid: u64,
-    pub bidder: Addr,
+    pub highest_bid: Uint128,
+    pub denom: Denom,
+    pub bidder: Option<Addr>,
 }
 
 #[cw_serde]
 pub struct AuctionItemResponse {
+    pub status: AuctionStatus,
+    pub start_time: Timestamp,
     pub end_time: Timestamp,
     pub cw721_address: Addr,
     pub token_id: String,
-    pub start_bid: u64,
+    pub bundle: Vec<Nft>,
+    pub auction_type: AuctionType,
+    pub start_bid: Uint128,
+    // whether the highest bid reaches the hidden reserve price, always true without a reserve
+    pub reserve_met: bool,
+    pub buy_now_price: Option<Uint128>,
+    pub time_extension: Option<TimeExtension>,
+    pub min_increment: Option<BidIncrement>,
+    pub denom: Denom,
+}
+
+#[cw_serde]
+pub struct BidResponse {
+    pub auction_id: u64,
+    pub seq: u32,
+    pub bidder: Addr,
+    pub amount: Uint128,
+    pub time: Timestamp,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct BidsResponse {
+    pub bids: Vec<BidResponse>,
+}
+
+#[cw_serde]
+pub enum AuctionStatus {
+    // waiting for the start time
+    Scheduled,
+    // open for bids, including the reveal phase of sealed-bid auctions
+    Active,
+    // ended and waiting for `EndAuction`
+    AwaitingSettlement,
+    Settled,
+    Canceled,
+}
+
+#[cw_serde]
+pub struct CurrentPriceResponse {
+    pub price: Uint128,
+    pub denom: Denom,
+}
+
+#[cw_serde]
+pub struct MinNextBidResponse {
+    pub min_bid: Uint128,
+    pub denom: Denom,
+}
+
+#[cw_serde]
+pub struct IntegrationResponse {
+    pub integration: Integration,
+}
+
+#[cw_serde]
+pub struct BalanceResponse {
+    pub amount: Uint128,
+    pub denom: Denom,
 }
 
 #[cw_serde]
 pub struct AuctionHistoryResponse {
+    pub idx: u32,
+    pub auction_id: u64,
+    pub bid_count: u32,
     pub end_time: Timestamp,
     pub seller: Addr,
     pub cw721_address: Addr,
     pub token_id: String,
-    pub highest_bid: u64,
+    pub bundle: Vec<Nft>,
+    pub highest_bid: Uint128,
+    pub denom: Denom,
     pub bidder: Addr,
+    pub fee: Uint128,
+    pub fee_collector: Addr,
+    pub royalty: Uint128,
+    pub royalties: Vec<Royalty>,
+    pub seller_amount: Uint128,
+}
+
+#[cw_serde]
+pub struct AuctionHistoriesResponse {
+    pub histories: Vec<AuctionHistoryResponse>,
+}
+
+#[cw_serde]
+pub struct AuctionHistoryCountResponse {
+    pub count: u32,
 }
@@ -1,40 +1,279 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Timestamp};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, Timestamp, Uint128};
+use cw20::Denom;
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+
+#[cw_serde]
+pub struct Config {
+    pub admin: Addr,
+    pub denoms: Vec<Denom>,
+    // nft contracts which can be auctioned
+    pub collections: Vec<Addr>,
+    // bounds of the auction time in seconds
+    pub max_expiration: u64,
+    pub min_duration: u64,
+    pub fee_bps: u64,
+    pub fee_collector: Addr,
+    // default minimum increment of english auctions
+    pub min_increment: Option<BidIncrement>,
+    // blocks new auctions, bids and settlements
+    pub paused: bool,
+}
+
+#[cw_serde]
+pub enum AuctionType {
+    English,
+    Dutch {
+        start_price: Uint128,
+        floor_price: Uint128,
+        decay: PriceDecay,
+    },
+    SealedBid {
+        reveal_duration: u64,
+        unrevealed_deposit: UnrevealedDeposit,
+    },
+}
+
+#[cw_serde]
+pub enum PriceDecay {
+    // the price falls evenly from the start price to the floor price by the end time
+    Linear {},
+    // the price falls by `amount` every `period` seconds until it reaches the floor price
+    Stepwise { period: u64, amount: Uint128 },
+}
+
+// what happens to the deposit of a sealed bid which is not revealed in time
+#[cw_serde]
+pub enum UnrevealedDeposit {
+    // the deposit is paid to the seller
+    Forfeit,
+    // the deposit is returned to the bidder
+    Refund,
+}
+
+// bids placed less than `window` seconds before the end time push the end time to `duration`
+// seconds after the bid, but never more than `max_extension` seconds past the original end time
+#[cw_serde]
+pub struct TimeExtension {
+    pub window: u64,
+    pub duration: u64,
+    pub max_extension: u64,
+}
+
+// a new bid must beat the highest bid by at least `amount`, or by `bps` basis points of it
+#[cw_serde]
+pub enum BidIncrement {
+    Absolute { amount: Uint128 },
+    Percentage { bps: u64 },
+}
 
 #[cw_serde]
 pub enum Mode {
     Progress,
+    // sealed-bid auctions take committed bids until the end time,
+    Commit,
+    // and then take reveals until the end of the reveal duration
+    Reveal,
     End,
+    Canceled,
 }
 
 #[cw_serde]
 pub struct State {
     pub mode: Mode,
+    pub auction_type: AuctionType,
+    pub start_time: Timestamp,
     pub end_time: Timestamp,
+    pub max_end_time: Timestamp,
+    pub time_extension: Option<TimeExtension>,
+    pub min_increment: Option<BidIncrement>,
     pub seller: Addr,
     pub cw721_address: Addr,
     pub token_id: String,
-    pub start_bid: u64,
+    // more nfts sold in the same lot
+    pub bundle: Vec<Nft>,
+    pub start_bid: Uint128,
+    pub reserve_price: Option<Uint128>,
+    pub buy_now_price: Option<Uint128>,
+    pub denom: Denom,
+    // platform fee of the config when the auction started, which the auction keeps
+    pub fee_bps: u64,
+    pub fee_collector: Addr,
+}
+
+#[cw_serde]
+pub struct Nft {
+    pub cw721_address: Addr,
+    pub token_id: String,
+}
+
+#[cw_serde]
+pub struct Royalty {
+    pub address: Addr,
+    pub amount: Uint128,
 }
 
 #[cw_serde]
 pub struct Bid {
-    pub highest_bid: u64,
+    pub highest_bid: Uint128,
+    pub bidder: Option<Addr>,
+}
+
+// an accepted or revealed bid in the bid log of an auction
+#[cw_serde]
+pub struct BidRecord {
     pub bidder: Addr,
+    pub amount: Uint128,
+    pub time: Timestamp,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct Commitment {
+    pub commitment: Binary,
+    pub deposit: Uint128,
+    pub revealed_bid: Option<Uint128>,
 }
 
 #[cw_serde]
 pub struct History {
+    pub auction_id: u64,
+    // number of bids in the bid log of the auction
+    pub bid_count: u32,
     pub end_time: Timestamp,
     pub seller: Addr,
     pub cw721_address: Addr,
     pub token_id: String,
-    pub highest_bid: u64,
+    pub bundle: Vec<Nft>,
+    pub highest_bid: Uint128,
+    pub denom: Denom,
     pub bidder: Addr,
+    pub fee: Uint128,
+    pub fee_collector: Addr,
+    // total of the royalties
+    pub royalty: Uint128,
+    pub royalties: Vec<Royalty>,
+    pub seller_amount: Uint128,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const AUCTIONS: Map<u64, State> = Map::new("auctions");
+pub const BIDS: Map<u64, Bid> = Map::new("bids");
+pub const COMMITMENTS: Map<(u64, &Addr), Commitment> = Map::new("commitments");
+pub const AUCTION_INDEX: Item<u64> = Item::new("auction_index");
+pub const BID_COUNTS: Map<u64, u32> = Map::new("bid_counts");
+
+pub struct BidRecordIndexes<'a> {
+    pub bidder: MultiIndex<'a, Addr, BidRecord, (u64, u32)>,
+}
+
+impl<'a> IndexList<BidRecord> for BidRecordIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<BidRecord>> + '_> {
+        let v: Vec<&dyn Index<BidRecord>> = vec![&self.bidder];
+        Box::new(v.into_iter())
+    }
+}
+
+// bid log keyed by auction id and the sequence of the bid in the auction, indexed by bidder
+pub fn bid_log<'a>() -> IndexedMap<'a, (u64, u32), BidRecord, BidRecordIndexes<'a>> {
+    let indexes = BidRecordIndexes {
+        bidder: MultiIndex::new(
+            |_pk: &[u8], b: &BidRecord| b.bidder.clone(),
+            "bid_log",
+            "bid_log__bidder",
+        ),
+    };
+    IndexedMap::new("bid_log", indexes)
+}
+
+pub struct HistoryIndexes<'a> {
+    pub seller: MultiIndex<'a, Addr, History, u32>,
+    pub bidder: MultiIndex<'a, Addr, History, u32>,
+    pub cw721_address: MultiIndex<'a, Addr, History, u32>,
+}
+
+impl<'a> IndexList<History> for HistoryIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<History>> + '_> {
+        let v: Vec<&dyn Index<History>> = vec![&self.seller, &self.bidder, &self.cw721_address];
+        Box::new(v.into_iter())
+    }
+}
+
+// histories are indexed by seller, bidder and nft contract
+pub fn histories<'a>() -> IndexedMap<'a, u32, History, HistoryIndexes<'a>> {
+    let indexes = HistoryIndexes {
+        seller: MultiIndex::new(
+            |_pk: &[u8], h: &History| h.seller.clone(),
+            "histories",
+            "histories__seller",
+        ),
+        bidder: MultiIndex::new(
+            |_pk: &[u8], h: &History| h.bidder.clone(),
+            "histories",
+            "histories__bidder",
+        ),
+        cw721_address: MultiIndex::new(
+            |_pk: &[u8], h: &History| h.cw721_address.clone(),
+            "histories",
+            "histories__cw721_address",
+        ),
+    };
+    IndexedMap::new("histories", indexes)
+}
+
+// max bid of the highest bidder of each english auction placed with `PlaceMaxBid`. the whole max
+// is escrowed and kept secret, while the highest bid shows the price it bids.
+pub const MAX_BIDS: Map<u64, Uint128> = Map::new("max_bids");
+
+// how the contract talks to the nft contract of a collection
+#[cw_serde]
+pub enum Integration {
+    // callable points of cw721-base-dynamiclink
+    DynamicLink,
+    // same as `DynamicLink`, and the creator royalty is read with the `royalty_info` callable
+    // point, which older builds of cw721-base-dynamiclink do not have
+    DynamicLinkWithRoyalty,
+    // cw721 queries and `TransferNft` messages, for collections without callable points
+    Standard,
 }
 
-pub const STATE: Item<State> = Item::new("state");
-pub const BID: Item<Bid> = Item::new("bid");
-pub const HISTORIES: Map<u32, History> = Map::new("histories");
+// integration of each collection, kept after the collection is removed for running auctions.
+// collections without an entry use the dynamic link.
+pub const INTEGRATIONS: Map<&Addr, Integration> = Map::new("integrations");
+
+// funds deposited to bid through the `place_bid_for` callable point, and payouts credited
+// inside it, keyed by owner and `balance_key` of the denom
+pub const BALANCES: Map<(&Addr, &str), Uint128> = Map::new("balances");
+
 pub const HISTORY_INDEX: Item<u32> = Item::new("history_index");
+
+// layout of 0.1.0, which ran a single auction at a time paid in cony
+#[cw_serde]
+pub struct LegacyState {
+    pub mode: Mode,
+    pub end_time: Timestamp,
+    pub seller: Addr,
+    pub cw721_address: Addr,
+    pub token_id: String,
+    pub start_bid: u64,
+}
+
+#[cw_serde]
+pub struct LegacyBid {
+    pub highest_bid: u64,
+    pub bidder: Addr,
+}
+
+#[cw_serde]
+pub struct LegacyHistory {
+    pub end_time: Timestamp,
+    pub seller: Addr,
+    pub cw721_address: Addr,
+    pub token_id: String,
+    pub highest_bid: u64,
+    pub bidder: Addr,
+}
+
+pub const LEGACY_STATE: Item<LegacyState> = Item::new("state");
+pub const LEGACY_BID: Item<LegacyBid> = Item::new("bid");
+pub const LEGACY_HISTORIES: Map<u32, LegacyHistory> = Map::new("histories");
@@ -7,7 +7,9 @@ use cw721::{
     AllNftInfoResponse, ApprovalResponse, ApprovalsResponse, ContractInfoResponse, NftInfoResponse,
     NumTokensResponse, OperatorsResponse, OwnerOfResponse, TokensResponse,
 };
-use cw721_base_dynamiclink::{ExecuteMsg, Extension, InstantiateMsg, MinterResponse, QueryMsg};
+use cw721_base_dynamiclink::{
+    ExecuteMsg, Extension, InstantiateMsg, MinterResponse, QueryMsg, RoyaltiesInfoResponse,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -36,4 +38,5 @@ fn main() {
     export_schema(&schema_for!(NumTokensResponse), &out_dir);
     export_schema(&schema_for!(OwnerOfResponse), &out_dir);
     export_schema(&schema_for!(TokensResponse), &out_dir);
+    export_schema(&schema_for!(RoyaltiesInfoResponse), &out_dir);
 }
@@ -1,13 +1,78 @@
-use cosmwasm_std::{callable_points, Binary, Empty, StdResult};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{callable_points, Binary, Empty, StdError, StdResult, Uint128};
 use cw2::set_contract_version;
 pub use cw721::OwnerOfResponse;
 pub use cw721_base::{ContractError, InstantiateMsg, MintMsg, MinterResponse};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 // Version info for migration
 const CONTRACT_NAME: &str = "crates.io:cw721-base-dynamiclink";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub type Extension = Option<Empty>;
+// Royalty settings of a token, following cw2981-royalties.
+#[cw_serde]
+#[derive(Default)]
+pub struct Metadata {
+    pub royalty_percentage: Option<u64>,
+    pub royalty_payment_address: Option<String>,
+}
+
+pub type Extension = Option<Metadata>;
+
+#[cw_serde]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+// Error of the `transfer_nft` callable point. `ContractError` of cw721-base cannot be
+// serialized, so its errors are carried by kind.
+#[derive(Error, Debug, PartialEq, Deserialize, Serialize)]
+pub enum TransferError {
+    #[error("{msg}")]
+    Std { msg: String },
+
+    #[error("caller is neither the owner nor approved")]
+    Unauthorized {},
+
+    #[error("token not found")]
+    NotFound {},
+
+    // the caller was approved for the token, or as an operator of its owner, but it expired
+    #[error("approval of the caller is expired")]
+    Expired {},
+
+    #[error("Approval not found for: {spender}")]
+    ApprovalNotFound { spender: String },
+}
+
+impl From<StdError> for TransferError {
+    fn from(err: StdError) -> Self {
+        match err {
+            StdError::NotFound { .. } => TransferError::NotFound {},
+            err => TransferError::Std {
+                msg: err.to_string(),
+            },
+        }
+    }
+}
+
+impl From<ContractError> for TransferError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Std(err) => err.into(),
+            ContractError::Ownership(_) => TransferError::Unauthorized {},
+            ContractError::Expired {} => TransferError::Expired {},
+            ContractError::ApprovalNotFound { spender } => {
+                TransferError::ApprovalNotFound { spender }
+            }
+            err => TransferError::Std {
+                msg: err.to_string(),
+            },
+        }
+    }
+}
 
 pub type Cw721BaseDynamicLinkContract<'a> =
     cw721_base::Cw721Contract<'a, Extension, Empty, Empty, Empty>;
@@ -19,7 +84,7 @@ pub mod entry {
     use super::*;
 
     use cosmwasm_std::entry_point;
-    use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult};
 
     // This makes a conscious choice on the various generics used by the contract
     #[entry_point]
@@ -44,6 +109,21 @@ pub mod entry {
         info: MessageInfo,
         msg: ExecuteMsg,
     ) -> Result<Response, ContractError> {
+        if let ExecuteMsg::Mint(MintMsg {
+            extension:
+                Some(Metadata {
+                    royalty_percentage: Some(royalty_percentage),
+                    ..
+                }),
+            ..
+        }) = &msg
+        {
+            if *royalty_percentage > 100 {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "royalty percentage must be between 0 and 100",
+                )));
+            }
+        }
         Cw721BaseDynamicLinkContract::default().execute(deps, env, info, msg)
     }
 
@@ -62,20 +142,55 @@ pub mod entry {
 mod callable_points {
     use super::*;
 
-    use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo};
+    use cosmwasm_std::{to_binary, Addr, Decimal, Deps, DepsMut, Env, MessageInfo};
 
     #[callable_point]
-    fn transfer_nft(deps: DepsMut, env: Env, recipient: String, token_id: String) -> bool {
+    fn transfer_nft(
+        mut deps: DepsMut,
+        env: Env,
+        recipient: String,
+        token_id: String,
+    ) -> Result<(), TransferError> {
         let info = MessageInfo {
-            sender: deps.api.get_caller_addr().unwrap(),
+            sender: deps.api.get_caller_addr()?,
             funds: vec![],
         };
-        if let Ok(_) = Cw721BaseDynamicLinkContract::default()
-            ._transfer_nft(deps, &env, &info, &recipient, &token_id)
+        Cw721BaseDynamicLinkContract::default()
+            ._transfer_nft(deps.branch(), &env, &info, &recipient, &token_id)
+            .map_err(|err| match err {
+                // cw721-base reports an expired approval as a missing one
+                ContractError::Ownership(_)
+                    if has_expired_approval(deps.as_ref(), &env, &info.sender, &token_id)
+                        .unwrap_or(false) =>
+                {
+                    TransferError::Expired {}
+                }
+                err => err.into(),
+            })?;
+        Ok(())
+    }
+
+    // Returns whether `spender` has an expired approval for the token, or as an operator of its
+    // owner.
+    fn has_expired_approval(
+        deps: Deps,
+        env: &Env,
+        spender: &Addr,
+        token_id: &str,
+    ) -> StdResult<bool> {
+        let contract = Cw721BaseDynamicLinkContract::default();
+        let token = contract.tokens.load(deps.storage, token_id)?;
+        if token
+            .approvals
+            .iter()
+            .any(|approval| approval.spender == *spender && approval.expires.is_expired(&env.block))
         {
-            return true;
+            return Ok(true);
         }
-        false
+        Ok(contract
+            .operators
+            .may_load(deps.storage, (&token.owner, spender))?
+            .map_or(false, |expires| expires.is_expired(&env.block)))
     }
 
     #[callable_point]
@@ -107,4 +222,31 @@ mod callable_points {
         };
         Cw721BaseDynamicLinkContract::default().query(deps, env, query_msg)
     }
+
+    // returns the royalty to pay for `sale_price` like cw2981 `RoyaltyInfo`
+    #[callable_point]
+    fn royalty_info(
+        deps: Deps,
+        _env: Env,
+        token_id: String,
+        sale_price: Uint128,
+    ) -> StdResult<Binary> {
+        let token = Cw721BaseDynamicLinkContract::default()
+            .tokens
+            .load(deps.storage, &token_id)?;
+        let response = match token.extension {
+            Some(Metadata {
+                royalty_percentage: Some(royalty_percentage),
+                royalty_payment_address: Some(address),
+            }) => RoyaltiesInfoResponse {
+                address,
+                royalty_amount: sale_price * Decimal::percent(royalty_percentage),
+            },
+            _ => RoyaltiesInfoResponse {
+                address: String::new(),
+                royalty_amount: Uint128::zero(),
+            },
+        };
+        to_binary(&response)
+    }
 }